@@ -0,0 +1,131 @@
+use std::hint::black_box;
+
+use bevy_math::{UVec2, Vec2};
+use chemical_simulation::{config::SimulationConfig, particle_grid::SpatialGrid, units::Length};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+const PARTICLE_COUNTS: [usize; 2] = [10_000, 100_000];
+const CUTOFF_PM: f32 = 800f32;
+// particles per cutoff sized cell, a bit denser than liquid water
+const PARTICLES_PER_CELL: f32 = 4f32;
+
+// particles spread evenly over a square box, the box grows with the count so the density stays
+// the same
+fn random_particles(count: usize) -> (Vec<Vec2>, SimulationConfig) {
+    let box_side_pm = (count as f32 / PARTICLES_PER_CELL).sqrt() * CUTOFF_PM;
+    let config = SimulationConfig {
+        box_size_pm: Vec2::splat(box_side_pm),
+        smoothing_distance: Length::from_pm(CUTOFF_PM),
+        ..Default::default()
+    };
+    let mut rng = StdRng::seed_from_u64(42);
+    let positions = (0..count)
+        .map(|_| {
+            Vec2::new(rng.random::<f32>() - 0.5f32, rng.random::<f32>() - 0.5f32) * box_side_pm
+        })
+        .collect();
+    (positions, config)
+}
+
+// the grid before the cell list, one vector per cell built every step and a fresh vector of
+// neighbouring cells for every particle
+struct NestedVecGrid {
+    size: UVec2,
+    cells: Vec<Vec<usize>>,
+}
+impl NestedVecGrid {
+    fn build(positions: &[Vec2], config: &SimulationConfig) -> NestedVecGrid {
+        let size = (config.box_size_pm / CUTOFF_PM).ceil().as_uvec2();
+        let mut grid = NestedVecGrid {
+            size,
+            cells: vec![Vec::new(); (size.x * size.y) as usize],
+        };
+        for (index, &position_pm) in positions.iter().enumerate() {
+            let cell = grid.cell_position(position_pm, config);
+            grid.cells[(cell.y * size.x + cell.x) as usize].push(index);
+        }
+        grid
+    }
+    fn cell_position(&self, position_pm: Vec2, config: &SimulationConfig) -> UVec2 {
+        ((position_pm + config.box_size_pm / 2f32) / CUTOFF_PM)
+            .as_uvec2()
+            .min(self.size - UVec2::ONE)
+    }
+    fn connected_cells(&self, position_pm: Vec2, config: &SimulationConfig) -> Vec<usize> {
+        let center = self.cell_position(position_pm, config);
+        let mut output = Vec::with_capacity(9);
+        for y in center.y.saturating_sub(1)..=(center.y + 1).min(self.size.y - 1) {
+            for x in center.x.saturating_sub(1)..=(center.x + 1).min(self.size.x - 1) {
+                output.push((y * self.size.x + x) as usize);
+            }
+        }
+        output
+    }
+}
+
+fn bench_build(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grid_build");
+    for count in PARTICLE_COUNTS {
+        let (positions, config) = random_particles(count);
+        group.bench_with_input(BenchmarkId::new("nested_vec", count), &count, |b, _| {
+            b.iter(|| NestedVecGrid::build(black_box(&positions), &config))
+        });
+        let mut grid = SpatialGrid::from_config(&config);
+        group.bench_with_input(BenchmarkId::new("cell_list", count), &count, |b, _| {
+            b.iter(|| grid.rebuild(black_box(&positions), |&position_pm| position_pm, &config))
+        });
+    }
+    group.finish();
+}
+
+// build plus counting the pairs closer than the cutoff, the work every force loop does on top of
+// the neighbour search
+fn bench_neighbour_search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("neighbour_search");
+    group.sample_size(20);
+    for count in PARTICLE_COUNTS {
+        let (positions, config) = random_particles(count);
+        group.bench_with_input(BenchmarkId::new("nested_vec", count), &count, |b, _| {
+            b.iter(|| {
+                let grid = NestedVecGrid::build(black_box(&positions), &config);
+                positions
+                    .iter()
+                    .map(|&position_pm| {
+                        let mut close_pairs = 0;
+                        for cell in grid.connected_cells(position_pm, &config) {
+                            for &index in &grid.cells[cell] {
+                                if positions[index].distance_squared(position_pm)
+                                    < CUTOFF_PM.powi(2)
+                                {
+                                    close_pairs += 1;
+                                }
+                            }
+                        }
+                        close_pairs
+                    })
+                    .sum::<usize>()
+            })
+        });
+        let mut grid = SpatialGrid::from_config(&config);
+        group.bench_with_input(BenchmarkId::new("cell_list", count), &count, |b, _| {
+            b.iter(|| {
+                grid.rebuild(black_box(&positions), |&position_pm| position_pm, &config);
+                positions
+                    .iter()
+                    .map(|&position_pm| {
+                        grid.neighbours(position_pm)
+                            .filter(|&index| {
+                                positions[index].distance_squared(position_pm) < CUTOFF_PM.powi(2)
+                            })
+                            .count()
+                    })
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_build, bench_neighbour_search);
+criterion_main!(benches);
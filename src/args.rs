@@ -0,0 +1,43 @@
+use anyhow::{Context, bail};
+
+const HEADLESS_FLAG: &str = "--headless";
+const CONFIG_FLAG: &str = "--config";
+const DEFAULT_HEADLESS_FRAMES: u32 = 1000;
+const USAGE: &str = "usage: [--headless [frames]] [--config <path.ron>]";
+
+pub struct Args {
+    // number of 60 fps frames to run without a window, `None` starts the normal windowed app
+    pub headless_frames: Option<u32>,
+    pub config_path: Option<String>,
+}
+impl Args {
+    pub fn parse(args: impl Iterator<Item = String>) -> anyhow::Result<Args> {
+        let mut args = args.peekable();
+        let mut output = Args {
+            headless_frames: None,
+            config_path: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                HEADLESS_FLAG => {
+                    // the number of frames is optional
+                    let frames = match args.next_if(|value| !value.starts_with("--")) {
+                        Some(value) => value.parse().with_context(|| {
+                            format!("`{value}` is not a valid number of headless frames")
+                        })?,
+                        None => DEFAULT_HEADLESS_FRAMES,
+                    };
+                    output.headless_frames = Some(frames);
+                }
+                CONFIG_FLAG => {
+                    let path = args
+                        .next()
+                        .with_context(|| format!("{CONFIG_FLAG} needs a path, {USAGE}"))?;
+                    output.config_path = Some(path);
+                }
+                _ => bail!("unknown argument `{arg}`, {USAGE}"),
+            }
+        }
+        Ok(output)
+    }
+}
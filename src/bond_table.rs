@@ -0,0 +1,229 @@
+use std::{collections::HashMap, fs, path::Path, sync::OnceLock};
+
+use anyhow::{Context, bail, ensure};
+use serde::Deserialize;
+
+use crate::{
+    config::SimulationConfig,
+    element::{Element, find_element_index},
+    units::{Energy, KJ_PER_MOL},
+};
+
+// UFF bond order correction, every bond order shortens the bond by ln(order) * this * radii sum
+const BOND_ORDER_SHORTENING: f32 = 0.1332;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BondEntry {
+    elements: (String, String),
+    order: u8,
+    length_pm: f32,
+    stiffness_kj_mol_pm2: f32,
+    dissociation_energy_kj_mol: f32,
+    #[serde(default)]
+    activation_energy_kj_mol: Option<f32>,
+    #[serde(default)]
+    morse_width_per_pm: Option<f32>,
+}
+#[derive(Clone, Copy)]
+pub struct BondParameters {
+    pub length_pm: f32,
+    pub stiffness: f32, // u / ns^2, F = -stiffness * (r - length)
+    // released when the bond forms and needed to break it
+    pub dissociation_energy: Energy,
+    // collision energy needed to form the bond
+    pub activation_energy: Energy,
+    // 1 / pm, how quickly the Morse well flattens out
+    pub morse_width: f32,
+}
+// activation energy is only known once the config is, so the table keeps it optional
+#[derive(Clone, Copy)]
+struct BondTableEntry {
+    parameters: BondParameters,
+    activation_energy: Option<Energy>,
+}
+
+// element indexes sorted from the smaller one and the bond order
+type BondKey = (u8, u8, u8);
+
+const BUNDLED_BONDS: &str = include_str!("../assets/data/bonds.ron");
+static BONDS: OnceLock<HashMap<BondKey, BondTableEntry>> = OnceLock::new();
+
+fn bonds() -> &'static HashMap<BondKey, BondTableEntry> {
+    BONDS.get_or_init(|| parse_bonds(BUNDLED_BONDS).expect("bundled bond table should be valid"))
+}
+// loads the bond table from `path` or the bundled one, needs the element table to be loaded and
+// has to run before the first bond gets created
+pub fn init_bond_table(path: Option<&Path>) -> anyhow::Result<()> {
+    let loaded_bonds = match path {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read bond table {}", path.display()))?;
+            parse_bonds(&text).with_context(|| format!("invalid bond table {}", path.display()))?
+        }
+        None => parse_bonds(BUNDLED_BONDS).context("invalid bundled bond table")?,
+    };
+    if BONDS.set(loaded_bonds).is_err() {
+        bail!("bond table was already loaded");
+    }
+    Ok(())
+}
+
+fn parse_bonds(text: &str) -> anyhow::Result<HashMap<BondKey, BondTableEntry>> {
+    let entries: Vec<BondEntry> = ron::from_str(text).context("failed to parse bond table")?;
+    let mut output = HashMap::with_capacity(entries.len());
+    for entry in entries {
+        let (symbol, other_symbol) = &entry.elements;
+        let element_index = find_element_index(symbol)
+            .with_context(|| format!("element `{symbol}` is not in the element table"))?;
+        let other_element_index = find_element_index(other_symbol)
+            .with_context(|| format!("element `{other_symbol}` is not in the element table"))?;
+        ensure!(
+            (1..=3).contains(&entry.order),
+            "bond {symbol}-{other_symbol} has order {}, only 1 to 3 is supported",
+            entry.order
+        );
+        ensure!(
+            entry.length_pm > 0f32 && entry.stiffness_kj_mol_pm2 > 0f32,
+            "bond {symbol}-{other_symbol} needs a positive length and stiffness"
+        );
+        ensure!(
+            entry.dissociation_energy_kj_mol > 0f32,
+            "bond {symbol}-{other_symbol} needs a positive dissociation energy"
+        );
+        ensure!(
+            entry.morse_width_per_pm.is_none_or(|width| width > 0f32),
+            "bond {symbol}-{other_symbol} needs a positive Morse width"
+        );
+        ensure!(
+            entry
+                .activation_energy_kj_mol
+                .is_none_or(|activation_energy| activation_energy >= 0f32),
+            "bond {symbol}-{other_symbol} can't have a negative activation energy"
+        );
+
+        let key = bond_key(element_index as u8, other_element_index as u8, entry.order);
+        let stiffness = entry.stiffness_kj_mol_pm2 * KJ_PER_MOL;
+        let dissociation_energy = Energy::from_kj_per_mol(entry.dissociation_energy_kj_mol);
+        let table_entry = BondTableEntry {
+            parameters: BondParameters {
+                length_pm: entry.length_pm,
+                stiffness,
+                dissociation_energy,
+                activation_energy: Energy::default(),
+                morse_width: entry
+                    .morse_width_per_pm
+                    .unwrap_or_else(|| morse_width(stiffness, dissociation_energy)),
+            },
+            activation_energy: entry.activation_energy_kj_mol.map(Energy::from_kj_per_mol),
+        };
+        ensure!(
+            output.insert(key, table_entry).is_none(),
+            "bond {symbol}-{other_symbol} with order {} is listed more than once",
+            entry.order
+        );
+    }
+    Ok(output)
+}
+fn bond_key(element_index: u8, other_element_index: u8, order: u8) -> BondKey {
+    (
+        element_index.min(other_element_index),
+        element_index.max(other_element_index),
+        order,
+    )
+}
+
+// `order` is the number of shared electron pairs (`Bond::electrons_used`)
+pub fn bond_parameters(
+    element: &Element,
+    other_element: &Element,
+    order: u8,
+    config: &SimulationConfig,
+) -> BondParameters {
+    let order = order.clamp(1, 3);
+    let key = bond_key(
+        element.atomic_number - 1,
+        other_element.atomic_number - 1,
+        order,
+    );
+    if let Some(table_entry) = bonds().get(&key) {
+        return BondParameters {
+            activation_energy: table_entry
+                .activation_energy
+                .unwrap_or(config.reaction_activation_energy_kj_mol),
+            ..table_entry.parameters
+        };
+    }
+
+    let radii_sum = (element.covalent_radius_pm + other_element.covalent_radius_pm) as f32;
+    let stiffness = config.default_bond_stiffness * order as f32;
+    let dissociation_energy = config.default_dissociation_energy_kj_mol * order as f32;
+    BondParameters {
+        length_pm: radii_sum * (1f32 - BOND_ORDER_SHORTENING * (order as f32).ln()),
+        stiffness,
+        dissociation_energy,
+        activation_energy: config.reaction_activation_energy_kj_mol,
+        morse_width: morse_width(stiffness, dissociation_energy),
+    }
+}
+// the Morse well with this width curves like the harmonic spring at the bond length
+fn morse_width(stiffness: f32, dissociation_energy: Energy) -> f32 {
+    (stiffness / (2f32 * dissociation_energy.u_pm2_ns2())).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::elements;
+
+    fn element(symbol: &str) -> &'static Element {
+        &elements()[find_element_index(symbol).unwrap()]
+    }
+
+    #[test]
+    fn bundled_table_parses() {
+        let bonds = parse_bonds(BUNDLED_BONDS).unwrap();
+        let hydrogen = find_element_index("H").unwrap() as u8;
+        assert_eq!(
+            bonds[&bond_key(hydrogen, hydrogen, 1)].parameters.length_pm,
+            74f32
+        );
+    }
+
+    #[test]
+    fn duplicate_bonds_are_rejected() {
+        let text = r#"[
+            (elements: ("H", "O"), order: 1, length_pm: 96.0, stiffness_kj_mol_pm2: 0.4628, dissociation_energy_kj_mol: 463.0),
+            (elements: ("O", "H"), order: 1, length_pm: 97.0, stiffness_kj_mol_pm2: 0.4628, dissociation_energy_kj_mol: 463.0),
+        ]"#;
+        let Err(error) = parse_bonds(text) else {
+            panic!("duplicate bond was accepted");
+        };
+        assert!(error.to_string().contains("more than once"), "{error}");
+    }
+
+    #[test]
+    fn fallback_shortens_higher_orders() {
+        // S-O isn't in the bundled table
+        let config = SimulationConfig::default();
+        let lengths_pm: Vec<f32> = (1..=3)
+            .map(|order| bond_parameters(element("S"), element("O"), order, &config).length_pm)
+            .collect();
+        let radii_sum = (element("S").covalent_radius_pm + element("O").covalent_radius_pm) as f32;
+        assert_eq!(lengths_pm[0], radii_sum);
+        assert!(lengths_pm[1] < lengths_pm[0]);
+        assert!(lengths_pm[2] < lengths_pm[1]);
+    }
+
+    #[test]
+    fn morse_width_matches_harmonic_curvature() {
+        // the Morse well D * (1 - e^(-a * x))^2 curves with 2 * D * a^2 at its bottom
+        let config = SimulationConfig::default();
+        for (symbol, other_symbol) in [("H", "H"), ("S", "O")] {
+            let parameters = bond_parameters(element(symbol), element(other_symbol), 1, &config);
+            let curvature =
+                2f32 * parameters.dissociation_energy.u_pm2_ns2() * parameters.morse_width.powi(2);
+            assert!((curvature / parameters.stiffness - 1f32).abs() < 1e-4);
+        }
+    }
+}
@@ -1,13 +1,17 @@
 use bevy::{color::palettes::css::GREY, math::*, prelude::*, sprite::Sprite};
+use chemical_simulation::{config::SimulationConfig, units::pm_to_world};
 
-pub const BOX_BOUNDS_SIZE_PIXELS: Vec2 = Vec2::new(1700f32, 1000f32);
 const BOX_SPRITE_PATH: &str = "sprites/box.png";
 pub const BOX_SPRITE_RESOLUTION: Vec2 = Vec2::new(50f32, 50f32);
 
-pub fn spawn_bounding_box(mut commands: Commands, asset_server: Res<AssetServer>) {
+pub fn spawn_bounding_box(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<SimulationConfig>,
+) {
     let mut sprite = Sprite::from_image(asset_server.load(BOX_SPRITE_PATH));
     sprite.color = Color::Srgba(GREY);
-    let scale = BOX_BOUNDS_SIZE_PIXELS / BOX_SPRITE_RESOLUTION;
+    let scale = pm_to_world(config.box_size_pm) / BOX_SPRITE_RESOLUTION;
 
     // Z works like layer so -1000 to make it stay in the background
     let transform = Transform {
@@ -0,0 +1,223 @@
+use bevy::{
+    color::palettes::css::YELLOW,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    window::PrimaryWindow,
+};
+use chemical_simulation::{
+    config::SimulationConfig,
+    simulation::Simulation,
+    simulation_plugin::SimulationCamera,
+    units::{WORLD_UNITS_PER_PM, pm_to_world, world_to_pm},
+};
+
+// every scroll line zooms in or out by this much
+const ZOOM_STEP: f32 = 1.1;
+// touchpads scroll in pixels, about this many make one line
+const PIXELS_PER_SCROLL_LINE: f32 = 20f32;
+// world units per screen pixel, from a single atom filling the window to a box much bigger than it
+const MIN_ZOOM_SCALE: f32 = 0.02;
+const MAX_ZOOM_SCALE: f32 = 50f32;
+// the whole box and a bit of space around it fit the window after a reset
+const VIEW_MARGIN: f32 = 1.1;
+// F picks the closest particle this near the cursor
+const FOLLOW_PICK_DISTANCE_PM: f32 = 50f32;
+
+// scroll zooms around the cursor, left drag pans, F follows the particle under the cursor and
+// Home fits the whole box back into the window
+#[derive(Component, Default)]
+pub struct CameraController {
+    pub followed_particle: Option<usize>,
+    // cursor position of the last frame of a drag, in window pixels
+    last_drag_position: Option<Vec2>,
+}
+
+pub fn spawn_camera(
+    mut commands: Commands,
+    config: Res<SimulationConfig>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let scale = q_window
+        .get_single()
+        .map(|window| fit_box_scale(window, &config))
+        .unwrap_or(1f32);
+    commands.spawn((
+        Camera2d,
+        OrthographicProjection {
+            scale,
+            ..OrthographicProjection::default_2d()
+        },
+        SimulationCamera,
+        CameraController::default(),
+        // the census chart has a second camera, the ui stays on this one
+        IsDefaultUiCamera,
+    ));
+}
+
+// world units per pixel that show the whole box, the box size doesn't depend on the window
+fn fit_box_scale(window: &Window, config: &SimulationConfig) -> f32 {
+    let box_size = pm_to_world(config.box_size_pm) * VIEW_MARGIN;
+    (box_size / window.size())
+        .max_element()
+        .clamp(MIN_ZOOM_SCALE, MAX_ZOOM_SCALE)
+}
+// window pixels go down, world units go up
+fn window_to_world_offset(offset: Vec2, scale: f32) -> Vec2 {
+    Vec2::new(offset.x, -offset.y) * scale
+}
+
+pub fn zoom_camera(
+    mut scroll_events: EventReader<MouseWheel>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(
+        &mut Transform,
+        &mut OrthographicProjection,
+        &CameraController,
+    )>,
+) {
+    let scroll_lines: f32 = scroll_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_SCROLL_LINE,
+        })
+        .sum();
+    let (Ok(window), Ok((mut transform, mut projection, controller))) =
+        (q_window.get_single(), q_camera.get_single_mut())
+    else {
+        return;
+    };
+    if scroll_lines == 0f32 {
+        return;
+    }
+
+    let old_scale = projection.scale;
+    projection.scale =
+        (old_scale * ZOOM_STEP.powf(-scroll_lines)).clamp(MIN_ZOOM_SCALE, MAX_ZOOM_SCALE);
+
+    // the point under the cursor stays in place, a followed particle stays in the middle
+    if controller.followed_particle.is_some() {
+        return;
+    }
+    if let Some(cursor_position) = window.cursor_position() {
+        let cursor_offset = cursor_position - window.size() / 2f32;
+        transform.translation +=
+            window_to_world_offset(cursor_offset, old_scale - projection.scale).extend(0f32);
+    }
+}
+
+pub fn pan_camera(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(
+        &mut Transform,
+        &OrthographicProjection,
+        &mut CameraController,
+    )>,
+) {
+    let (Ok(window), Ok((mut transform, projection, mut controller))) =
+        (q_window.get_single(), q_camera.get_single_mut())
+    else {
+        return;
+    };
+    if !mouse_buttons.pressed(MouseButton::Left) {
+        controller.last_drag_position = None;
+        return;
+    }
+
+    let cursor_position = window.cursor_position();
+    if let (Some(cursor_position), Some(last_drag_position)) =
+        (cursor_position, controller.last_drag_position)
+    {
+        let drag = cursor_position - last_drag_position;
+        if drag != Vec2::ZERO {
+            // dragging takes the view away from the followed particle
+            controller.followed_particle = None;
+            transform.translation -= window_to_world_offset(drag, projection.scale).extend(0f32);
+        }
+    }
+    controller.last_drag_position = cursor_position;
+}
+
+pub fn select_followed_particle(
+    keys: Res<ButtonInput<KeyCode>>,
+    simulation: Res<Simulation>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(&Camera, &GlobalTransform, &mut CameraController)>,
+) {
+    if !keys.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+    let (Ok(window), Ok((camera, camera_transform, mut controller))) =
+        (q_window.get_single(), q_camera.get_single_mut())
+    else {
+        return;
+    };
+
+    // nothing under the cursor stops following
+    let cursor_pm = window
+        .cursor_position()
+        .and_then(|cursor_position| {
+            camera
+                .viewport_to_world_2d(camera_transform, cursor_position)
+                .ok()
+        })
+        .map(world_to_pm);
+    controller.followed_particle = cursor_pm.and_then(|cursor_pm| {
+        simulation
+            .particles
+            .iter()
+            .map(|particle| (particle.index, particle.position_pm.distance(cursor_pm)))
+            .filter(|(_, distance_pm)| *distance_pm <= FOLLOW_PICK_DISTANCE_PM)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    });
+}
+
+pub fn reset_camera(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<SimulationConfig>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut q_camera: Query<(
+        &mut Transform,
+        &mut OrthographicProjection,
+        &mut CameraController,
+    )>,
+) {
+    if !keys.just_pressed(KeyCode::Home) {
+        return;
+    }
+    let (Ok(window), Ok((mut transform, mut projection, mut controller))) =
+        (q_window.get_single(), q_camera.get_single_mut())
+    else {
+        return;
+    };
+    // the box is centered on the origin
+    transform.translation = Vec3::new(0f32, 0f32, transform.translation.z);
+    projection.scale = fit_box_scale(window, &config);
+    controller.followed_particle = None;
+}
+
+pub fn follow_particle(
+    simulation: Res<Simulation>,
+    mut q_camera: Query<(&mut Transform, &CameraController)>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((mut transform, controller)) = q_camera.get_single_mut() else {
+        return;
+    };
+    let Some(particle) = controller
+        .followed_particle
+        .and_then(|index| simulation.particles.get(index))
+    else {
+        return;
+    };
+
+    let position = pm_to_world(particle.position_pm);
+    transform.translation = position.extend(transform.translation.z);
+    gizmos.circle_2d(
+        Isometry2d::from_translation(position),
+        FOLLOW_PICK_DISTANCE_PM * WORLD_UNITS_PER_PM,
+        YELLOW,
+    );
+}
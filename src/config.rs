@@ -0,0 +1,314 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, ensure};
+use bevy_math::Vec2;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bond_potential::BondPotential,
+    element,
+    thermostat::Thermostat,
+    units::{Energy, Length, Temperature, Time},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Integrator {
+    // semi-implicit (symplectic) Euler, velocity first and then position with the new velocity.
+    // First order, the energy error is larger than with Verlet but stays bounded instead of drifting
+    Euler,
+    // second order and time reversible, keeps energy stable
+    VelocityVerlet,
+}
+impl Integrator {
+    pub fn next(self) -> Integrator {
+        match self {
+            Integrator::Euler => Integrator::VelocityVerlet,
+            Integrator::VelocityVerlet => Integrator::Euler,
+        }
+    }
+}
+
+// every parameter that can change between experiments without recompiling,
+// missing fields in a config file fall back to the defaults below
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    // physics settings
+    // units: pm, ns, u (atomic mass units), e (elementary charge) and kJ/mol, see `units`
+    pub run_physics: bool,
+    pub time_scale_ns: Time, // simulated nano seconds per real second
+    // every physics step advances the simulation by exactly this much, independent of fps
+    pub timestep_ns: Time,
+    // steps that didn't fit into a slow frame are dropped above this, slowing the simulation down
+    pub max_steps_per_frame: u32,
+    // splits a fixed step into shorter ones when particles would move too far in one
+    pub adaptive_timestep: bool,
+    pub max_step_displacement_pm: Length,
+    // the adaptive timestep doesn't go below `timestep_ns` times this
+    pub min_timestep_fraction: f32,
+    pub integrator: Integrator,
+
+    // temperature
+    pub thermostat: Thermostat,
+    pub target_temperature_k: Temperature,
+    // how quickly the thermostat pulls the temperature to the target
+    pub thermostat_time_constant_ns: Time,
+
+    // bonds
+    // covalent bonds form up to this many equilibrium lengths from `bond_table`, they break once
+    // they hold more energy than their dissociation energy
+    pub covalent_bond_forming_factor: f32,
+    // u / ns^2 per bond order, for element pairs missing in the bond table
+    pub default_bond_stiffness: f32,
+    // per bond order, for element pairs missing in the bond table
+    pub default_dissociation_energy_kj_mol: Energy,
+    // shape of the covalent bond well, Harmonic or Morse
+    pub bond_potential: BondPotential,
+    // collision energy along the line between two atoms needed to bond them, the bond table can
+    // set it per bond
+    pub reaction_activation_energy_kj_mol: Energy,
+    // u * pm^2 / ns^2 / rad^2, how strongly bonds keep their VSEPR angles
+    pub angle_stiffness: f32,
+    // bonds between elements with a bigger electronegativity difference are ionic
+    pub ionic_electronegativity_difference: f32,
+    // ionic bonds form up to this many contact distances
+    pub ionic_bond_forming_factor: f32,
+    // Born exponent of the short range repulsion between ions
+    pub ionic_repulsion_exponent: f32,
+    // e moved per shared electron and unit of electronegativity difference in covalent bonds
+    pub partial_charge_per_electronegativity: f32,
+
+    // box
+    pub box_size_pm: Vec2,
+    pub collision_damping: f32,
+    // size of a grid cell and cutoff of non-bonded forces, particles further apart than this
+    // don't interact
+    pub smoothing_distance: Length,
+
+    // spawning
+    pub particles_count: u32,
+    pub particles_layers: u32,
+    pub particles_spacing: Length,
+    // symbols of elements picked at random for every spawned particle
+    pub elements_to_spawn: Vec<String>,
+    // spawned velocities follow the Maxwell-Boltzmann distribution for this temperature
+    pub initial_temperature_k: Temperature,
+    // seeds spawning and the Langevin thermostat, runs with the same seed and config match bit for
+    // bit, a random one is used when not set
+    pub seed: Option<u64>,
+
+    // energy samples kept in `EnergyHistory`, one per fixed step
+    pub energy_history_length: usize,
+    // csv file the energy history is written to, at the end of headless runs or with E
+    pub energy_log_path: Option<String>,
+
+    // element table file, the bundled `assets/data/elements.ron` is used when not set
+    pub elements_path: Option<String>,
+    // bond table file, the bundled `assets/data/bonds.ron` is used when not set
+    pub bonds_path: Option<String>,
+}
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig {
+            run_physics: true,
+            time_scale_ns: Time::from_ns(1e-4f32),
+            // 0.5 fs, atoms move ~1 pm per fs, 200 steps per real second with the time scale above
+            timestep_ns: Time::from_fs(0.5f32),
+            max_steps_per_frame: 10,
+            adaptive_timestep: true,
+            // a few percent of a bond length
+            max_step_displacement_pm: Length::from_pm(3f32),
+            min_timestep_fraction: 1f32 / 64f32,
+            integrator: Integrator::VelocityVerlet,
+
+            thermostat: Thermostat::Berendsen,
+            target_temperature_k: Temperature::from_k(300f32),
+            // 0.1 ps, about a second of real time with the default time scale
+            thermostat_time_constant_ns: Time::from_ns(1e-4f32),
+
+            covalent_bond_forming_factor: 1.5f32,
+            // ~0.3 kJ/mol/pm^2, close to a C-C single bond
+            default_bond_stiffness: 3e11f32,
+            // close to a C-C single bond
+            default_dissociation_energy_kj_mol: Energy::from_kj_per_mol(350f32),
+            bond_potential: BondPotential::Harmonic,
+            // about twice the thermal energy at room temperature
+            reaction_activation_energy_kj_mol: Energy::from_kj_per_mol(5f32),
+            // ~400 kJ/mol/rad^2, close to the H-O-H angle of water models
+            angle_stiffness: 4e14f32,
+            ionic_electronegativity_difference: 1.7f32,
+            ionic_bond_forming_factor: 1.5f32,
+            ionic_repulsion_exponent: 8f32,
+            // gives water hydrogens ~+0.4 e like common water models
+            partial_charge_per_electronegativity: 0.33f32,
+
+            box_size_pm: Vec2::new(1700f32, 1000f32),
+            collision_damping: 0.5f32,
+            // ~2.5 sigma of oxygen
+            smoothing_distance: Length::from_pm(800f32),
+
+            // atoms are ~300 pm wide with van der Waals forces, so the box only fits a few dozen
+            particles_count: 24,
+            particles_layers: 4,
+            particles_spacing: Length::from_pm(250f32),
+            elements_to_spawn: vec!["O".to_string(), "H".to_string()],
+            initial_temperature_k: Temperature::from_k(300f32),
+            seed: None,
+
+            energy_history_length: 10_000,
+            energy_log_path: None,
+
+            elements_path: None,
+            bonds_path: None,
+        }
+    }
+}
+impl SimulationConfig {
+    // reads a config from a RON file
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<SimulationConfig> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        ron::from_str(&text)
+            .with_context(|| format!("failed to parse config file {}", path.display()))
+    }
+    // checks values that would otherwise panic later, needs the element table to be loaded
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(
+            !self.elements_to_spawn.is_empty(),
+            "elements_to_spawn can't be empty"
+        );
+        ensure!(
+            self.time_scale_ns > Time::default() && self.timestep_ns > Time::default(),
+            "time_scale_ns and timestep_ns have to be positive"
+        );
+        ensure!(
+            self.max_steps_per_frame > 0,
+            "max_steps_per_frame has to be at least 1"
+        );
+        ensure!(
+            self.max_step_displacement_pm > Length::default(),
+            "max_step_displacement_pm has to be positive"
+        );
+        ensure!(
+            self.min_timestep_fraction > 0f32 && self.min_timestep_fraction <= 1f32,
+            "min_timestep_fraction has to be in (0, 1]"
+        );
+        ensure!(
+            self.initial_temperature_k >= Temperature::default(),
+            "initial_temperature_k can't be negative"
+        );
+        ensure!(
+            self.target_temperature_k > Temperature::default(),
+            "target_temperature_k has to be positive"
+        );
+        ensure!(
+            self.thermostat_time_constant_ns > Time::default(),
+            "thermostat_time_constant_ns has to be positive"
+        );
+        ensure!(
+            self.particles_layers > 0,
+            "particles_layers has to be at least 1"
+        );
+        ensure!(
+            self.smoothing_distance > Length::default(),
+            "smoothing_distance has to be positive"
+        );
+        ensure!(
+            self.box_size_pm.x > 0f32 && self.box_size_pm.y > 0f32,
+            "box_size_pm has to be positive"
+        );
+        ensure!(
+            self.default_dissociation_energy_kj_mol > Energy::default(),
+            "default_dissociation_energy_kj_mol has to be positive"
+        );
+        ensure!(
+            self.reaction_activation_energy_kj_mol >= Energy::default(),
+            "reaction_activation_energy_kj_mol can't be negative"
+        );
+        ensure!(
+            (0f32..=1f32).contains(&self.collision_damping),
+            "collision_damping has to be in [0, 1]"
+        );
+        ensure!(
+            self.energy_history_length > 0,
+            "energy_history_length has to be at least 1"
+        );
+        // the Born repulsion has to fall off faster than the Coulomb pull, or ions never stop
+        ensure!(
+            self.ionic_repulsion_exponent > 1f32,
+            "ionic_repulsion_exponent has to be bigger than 1"
+        );
+        for symbol in &self.elements_to_spawn {
+            ensure!(
+                element::find_element_index(symbol).is_some(),
+                "element `{symbol}` from elements_to_spawn is not in the element table"
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_is_valid() {
+        SimulationConfig::default().validate().unwrap();
+    }
+
+    #[test]
+    fn collision_damping_has_to_be_a_fraction() {
+        for collision_damping in [-0.1f32, 1.5f32, f32::NAN] {
+            let config = SimulationConfig {
+                collision_damping,
+                ..Default::default()
+            };
+            let Err(error) = config.validate() else {
+                panic!("collision_damping {collision_damping} was accepted");
+            };
+            assert!(error.to_string().contains("collision_damping"), "{error}");
+        }
+        for collision_damping in [0f32, 1f32] {
+            let config = SimulationConfig {
+                collision_damping,
+                ..Default::default()
+            };
+            config.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn energy_history_can_not_be_empty() {
+        let config = SimulationConfig {
+            energy_history_length: 0,
+            ..Default::default()
+        };
+        let Err(error) = config.validate() else {
+            panic!("energy_history_length 0 was accepted");
+        };
+        assert!(
+            error.to_string().contains("energy_history_length"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn ionic_repulsion_exponent_has_to_be_above_one() {
+        for ionic_repulsion_exponent in [1f32, 0f32, -8f32, f32::NAN] {
+            let config = SimulationConfig {
+                ionic_repulsion_exponent,
+                ..Default::default()
+            };
+            let Err(error) = config.validate() else {
+                panic!("ionic_repulsion_exponent {ionic_repulsion_exponent} was accepted");
+            };
+            assert!(
+                error.to_string().contains("ionic_repulsion_exponent"),
+                "{error}"
+            );
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+
+use crate::{
+    particle::Particle,
+    particle_physics,
+    simulation::Simulation,
+    units::{Energy, Mass},
+};
+
+// energies of the whole system at one moment
+#[derive(Clone, Copy, Default)]
+pub struct EnergySample {
+    pub time_ns: f64,
+    pub kinetic: Energy,
+    pub bonds: Energy,
+    pub angles: Energy,
+    pub non_bonded: Energy,
+    // summed since the start, negative when the walls or thermostat took energy away
+    pub wall_work: Energy,
+    pub thermostat_work: Energy,
+}
+impl EnergySample {
+    pub fn potential(&self) -> Energy {
+        self.bonds + self.angles + self.non_bonded
+    }
+    pub fn total(&self) -> Energy {
+        self.kinetic + self.potential()
+    }
+    // total energy minus everything added from outside, stays flat when the integration is good
+    pub fn conserved(&self) -> Energy {
+        self.total() - self.wall_work - self.thermostat_work
+    }
+}
+
+pub fn kinetic_energy(particles: &[Particle]) -> Energy {
+    particles
+        .iter()
+        .map(|particle| {
+            Energy::kinetic(
+                Mass::from_u(particle.element().mass_u),
+                particle.velocity_pm_ns,
+            )
+        })
+        .sum()
+}
+pub fn measure_energy(simulation: &Simulation) -> EnergySample {
+    let potential = particle_physics::potential_energy(
+        &simulation.particles,
+        &simulation.grid,
+        &simulation.config,
+    );
+    EnergySample {
+        time_ns: simulation.elapsed_ns,
+        kinetic: kinetic_energy(&simulation.particles),
+        bonds: potential.bonds,
+        angles: potential.angles,
+        non_bonded: potential.non_bonded,
+        wall_work: simulation.wall_work,
+        thermostat_work: simulation.thermostat_work,
+    }
+}
+
+// last `capacity` energy samples, the oldest one is dropped when it is full
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct EnergyHistory {
+    samples: VecDeque<EnergySample>,
+    capacity: usize,
+}
+impl EnergyHistory {
+    pub fn new(capacity: usize) -> EnergyHistory {
+        EnergyHistory {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+    pub fn push(&mut self, sample: EnergySample) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+    pub fn samples(&self) -> impl Iterator<Item = &EnergySample> {
+        self.samples.iter()
+    }
+    pub fn latest(&self) -> Option<&EnergySample> {
+        self.samples.back()
+    }
+    pub fn write_csv(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file = File::create(path)
+            .with_context(|| format!("failed to create energy log {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        writeln!(
+            writer,
+            "time_ns,kinetic_kj_mol,bonds_kj_mol,angles_kj_mol,non_bonded_kj_mol,total_kj_mol,wall_work_kj_mol,thermostat_work_kj_mol,conserved_kj_mol"
+        )?;
+        for sample in &self.samples {
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{}",
+                sample.time_ns,
+                sample.kinetic.kj_per_mol(),
+                sample.bonds.kj_per_mol(),
+                sample.angles.kj_per_mol(),
+                sample.non_bonded.kj_per_mol(),
+                sample.total().kj_per_mol(),
+                sample.wall_work.kj_per_mol(),
+                sample.thermostat_work.kj_per_mol(),
+                sample.conserved().kj_per_mol()
+            )?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("failed to write energy log {}", path.display()))
+    }
+}
@@ -1,44 +1,171 @@
-use bevy::prelude::*;
+use std::{collections::HashSet, fs, path::Path, sync::OnceLock};
 
+use anyhow::{Context, bail, ensure};
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct Element {
-    pub radious_pm: u16, // pico meters
-    pub mass_u: u16,     //  units
-    pub name: &'static str,
-    pub symbol: &'static str,
-    pub color: Srgba,
+    pub atomic_number: u8,
+    pub symbol: String,
+    pub name: String,
+    pub mass_u: f32, //  units
+    pub covalent_radius_pm: u16,
+    pub van_der_waals_radius_pm: u16,
+    // Pauling scale, noble gases don't have one
+    pub electronegativity: Option<f32>,
+    pub valence_electrons: u8,
     pub connected_electrons_needed: u8,
+    // kJ/mol, depth of the van der Waals well
+    pub lennard_jones_epsilon_kj_mol: f32,
+    // distance where the van der Waals potential crosses zero
+    pub lennard_jones_sigma_pm: f32,
+    pub oxidation_states: Vec<i8>,
+    // sRGB bytes, written as "#RRGGBB" in the table, the renderer turns them into its own colors
+    #[serde(deserialize_with = "deserialize_hex_color")]
+    pub color: [u8; 3],
+}
 
-    pub valence_electrons: u8,
+const BUNDLED_ELEMENTS: &str = include_str!("../assets/data/elements.ron");
+static ELEMENTS: OnceLock<Vec<Element>> = OnceLock::new();
+
+// element table indexed by `Particle::element_index`, the bundled one is used when
+// `init_elements` wasn't called before
+pub fn elements() -> &'static [Element] {
+    ELEMENTS.get_or_init(|| {
+        parse_elements(BUNDLED_ELEMENTS).expect("bundled element table should be valid")
+    })
+}
+// loads the element table from `path` or the bundled one, has to run before the first `elements()`
+pub fn init_elements(path: Option<&Path>) -> anyhow::Result<&'static [Element]> {
+    let loaded_elements = match path {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("failed to read element table {}", path.display()))?;
+            parse_elements(&text)
+                .with_context(|| format!("invalid element table {}", path.display()))?
+        }
+        None => parse_elements(BUNDLED_ELEMENTS).context("invalid bundled element table")?,
+    };
+    if ELEMENTS.set(loaded_elements).is_err() {
+        bail!("element table was already loaded");
+    }
+    Ok(elements())
+}
+pub fn find_element_index(symbol: &str) -> Option<usize> {
+    elements()
+        .iter()
+        .position(|element| element.symbol == symbol)
 }
 
-// for better accuracy other unit than unit for mass might be better
-pub const ELEMENTS: [Element; 3] = [
-    Element {
-        valence_electrons: 6,
-        radious_pm: 1, // not right
-        mass_u: 16,
-        name: "Oxygen",
-        symbol: "O",
-        connected_electrons_needed: 2,
+pub fn parse_elements(text: &str) -> anyhow::Result<Vec<Element>> {
+    let elements: Vec<Element> = ron::from_str(text).context("failed to parse element table")?;
+    ensure!(!elements.is_empty(), "element table is empty");
+    // element index is stored as u8 in particles
+    ensure!(
+        elements.len() <= u8::MAX as usize,
+        "element table has more than {} elements",
+        u8::MAX
+    );
 
-        color: Srgba::WHITE,
-    },
-    Element {
-        valence_electrons: 1,
-        radious_pm: 1, // not right
-        mass_u: 1,
-        name: "Hydrogen",
+    let mut symbols = HashSet::new();
+    for (index, element) in elements.iter().enumerate() {
+        validate_element(element, index)
+            .with_context(|| format!("invalid element `{}` at index {index}", element.symbol))?;
+        ensure!(
+            symbols.insert(element.symbol.as_str()),
+            "element symbol `{}` is used more than once",
+            element.symbol
+        );
+    }
+    Ok(elements)
+}
+fn validate_element(element: &Element, index: usize) -> anyhow::Result<()> {
+    ensure!(
+        element.atomic_number as usize == index + 1,
+        "atomic number {} doesn't match its position, elements have to be sorted without gaps",
+        element.atomic_number
+    );
+    ensure!(!element.symbol.is_empty(), "symbol is empty");
+    ensure!(element.mass_u > 0f32, "mass has to be positive");
+    ensure!(
+        element.covalent_radius_pm > 0 && element.van_der_waals_radius_pm > 0,
+        "radii have to be positive"
+    );
+    ensure!(
+        element.lennard_jones_epsilon_kj_mol >= 0f32 && element.lennard_jones_sigma_pm > 0f32,
+        "Lennard-Jones epsilon can't be negative and sigma has to be positive"
+    );
+    if let Some(electronegativity) = element.electronegativity {
+        ensure!(
+            electronegativity > 0f32 && electronegativity <= 4f32,
+            "electronegativity {electronegativity} is outside of the Pauling scale"
+        );
+    }
+    ensure!(
+        element.connected_electrons_needed <= element.valence_electrons,
+        "connected electrons needed can't be bigger than valence electrons"
+    );
+    ensure!(
+        element
+            .oxidation_states
+            .iter()
+            .all(|state| (-4..=8).contains(state)),
+        "oxidation states have to be between -4 and 8"
+    );
+    Ok(())
+}
+
+fn deserialize_hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 3], D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    let invalid = || serde::de::Error::custom(format!("color {hex:?} isn't written as #RRGGBB"));
+    let digits = hex.strip_prefix('#').unwrap_or(&hex);
+    if digits.len() != 6 || !digits.is_ascii() {
+        return Err(invalid());
+    }
+    let mut color = [0u8; 3];
+    for (channel, value) in color.iter_mut().enumerate() {
+        *value =
+            u8::from_str_radix(&digits[channel * 2..channel * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HYDROGEN: &str = r##"(
+        atomic_number: 1,
         symbol: "H",
-        color: Srgba::RED,
+        name: "Hydrogen",
+        mass_u: 1.008,
+        covalent_radius_pm: 31,
+        van_der_waals_radius_pm: 120,
+        electronegativity: Some(2.20),
+        valence_electrons: 1,
         connected_electrons_needed: 1,
-    },
-    Element {
-        valence_electrons: 4,
-        radious_pm: 1, // not right
-        mass_u: 12,
-        name: "Carbon",
-        symbol: "C",
-        color: Srgba::BLACK,
-        connected_electrons_needed: 4,
-    },
-];
+        lennard_jones_epsilon_kj_mol: 0.1841,
+        lennard_jones_sigma_pm: 257.1,
+        oxidation_states: [-1, 1],
+        color: "#FFFFFF",
+    )"##;
+
+    #[test]
+    fn bundled_table_parses() {
+        let elements = parse_elements(BUNDLED_ELEMENTS).unwrap();
+        assert_eq!(elements[0].symbol, "H");
+        assert_eq!(elements[0].color, [255, 255, 255]);
+        let carbon = &elements[find_element_index("C").unwrap()];
+        assert_eq!(carbon.atomic_number, 6);
+    }
+
+    #[test]
+    fn duplicate_symbols_are_rejected() {
+        let second_hydrogen = HYDROGEN.replace("atomic_number: 1", "atomic_number: 2");
+        let Err(error) = parse_elements(&format!("[{HYDROGEN}, {second_hydrogen}]")) else {
+            panic!("duplicate symbol was accepted");
+        };
+        assert!(error.to_string().contains("more than once"), "{error}");
+    }
+}
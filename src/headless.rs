@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use chemical_simulation::{
+    config::SimulationConfig, diagnostics::EnergyHistory, molecules::Molecules,
+    simulation::Simulation, simulation_plugin::SimulationPlugin,
+};
+
+// every headless step advances the clock as if the app was running at 60 fps
+const HEADLESS_FRAME_TIME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Runs the physics without a window or renderer and prints a summary at the end.
+pub fn run_headless(frames: u32, config: SimulationConfig) -> anyhow::Result<()> {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins.set(crate::task_pool_plugin()),
+        SimulationPlugin { config },
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(HEADLESS_FRAME_TIME));
+    app.finish();
+    app.cleanup();
+
+    let start = Instant::now();
+    for _ in 0..frames {
+        app.update();
+    }
+    print_summary(&app, frames, start.elapsed());
+
+    let simulation = app.world().resource::<Simulation>();
+    if let Some(path) = &simulation.config.energy_log_path {
+        app.world().resource::<EnergyHistory>().write_csv(path)?;
+        println!("  energy log: {path}");
+    }
+    Ok(())
+}
+
+fn print_summary(app: &App, frames: u32, elapsed: Duration) {
+    let simulation = app.world().resource::<Simulation>();
+
+    let particles_count = simulation.particles.len();
+    let mut nan_count = 0;
+    let mut speed_sum = 0f32;
+    for particle in &simulation.particles {
+        if particle.position_pm.is_nan() || particle.velocity_pm_ns.is_nan() {
+            nan_count += 1;
+            continue;
+        }
+        speed_sum += particle.velocity_pm_ns.length();
+    }
+    let valid_particles = (particles_count - nan_count).max(1);
+
+    println!("headless run finished");
+    println!("  frames: {frames}");
+    println!("  physics steps: {}", simulation.steps);
+    println!("  simulated time: {} ns", simulation.elapsed_ns);
+    println!("  last timestep: {} ns", simulation.current_timestep.ns());
+    println!("  wall time: {:.3} s", elapsed.as_secs_f32());
+    println!("  particles: {particles_count}");
+    println!("  bonds: {}", simulation.bonds_count());
+    let census = app.world().resource::<Molecules>().census();
+    let census: Vec<String> = census
+        .iter()
+        .map(|(formula, count)| format!("{count} {formula}"))
+        .collect();
+    println!("  molecules: {}", census.join(", "));
+    println!("  mean speed: {} pm/ns", speed_sum / valid_particles as f32);
+    println!("  temperature: {:.1} K", simulation.temperature().k());
+    if let Some(energy) = app.world().resource::<EnergyHistory>().latest() {
+        println!(
+            "  energy: total {:.2} kJ/mol, conserved {:.2} kJ/mol",
+            energy.total().kj_per_mol(),
+            energy.conserved().kj_per_mol()
+        );
+    }
+    println!("  particles with NaN state: {nan_count}");
+    println!(
+        "  particles outside the box: {}",
+        simulation.out_of_bounds_particles().len()
+    );
+    println!("  state checksum: {:016x}", simulation.state_checksum());
+}
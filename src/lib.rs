@@ -0,0 +1,31 @@
+#[path = "physics/angles.rs"]
+pub mod angles;
+#[path = "physics/bond_potential.rs"]
+pub mod bond_potential;
+pub mod bond_table;
+#[path = "physics/collisions.rs"]
+pub mod collisions;
+pub mod config;
+pub mod diagnostics;
+#[path = "physics/electrostatics.rs"]
+pub mod electrostatics;
+pub mod element;
+#[path = "physics/lennard_jones.rs"]
+pub mod lennard_jones;
+pub mod molecules;
+pub mod particle;
+pub mod particle_grid;
+#[path = "physics/particle_physics.rs"]
+pub mod particle_physics;
+pub mod particles_spawning;
+#[path = "physics/player_interaction_physics.rs"]
+pub mod player_interaction_physics;
+pub mod random;
+#[path = "physics/reactions.rs"]
+pub mod reactions;
+pub mod simulation;
+#[cfg(feature = "bevy")]
+pub mod simulation_plugin;
+#[path = "physics/thermostat.rs"]
+pub mod thermostat;
+pub mod units;
@@ -1,54 +1,99 @@
-mod Units;
+mod args;
 mod bounding_box;
-#[path = "physics/collisions.rs"]
-mod collisions;
-pub mod element;
-pub mod particle;
-mod particle_grid;
-#[path = "physics/particle_physics.rs"]
-mod particle_physics;
-mod particles_spawning;
+mod camera_controller;
+mod headless;
 mod particles_visuals;
-#[path = "physics/player_interaction_physics.rs"]
-mod player_interaction_physics;
 mod ui_handler;
 use bevy::{core::TaskPoolThreadAssignmentPolicy, prelude::*, tasks::available_parallelism};
+use chemical_simulation::{
+    bond_table, config::SimulationConfig, element, simulation_plugin::SimulationPlugin,
+};
+
+fn main() -> anyhow::Result<()> {
+    let args = args::Args::parse(std::env::args().skip(1))?;
+    let config = match &args.config_path {
+        Some(path) => SimulationConfig::load(path)?,
+        None => SimulationConfig::default(),
+    };
+    element::init_elements(config.elements_path.as_deref().map(std::path::Path::new))?;
+    bond_table::init_bond_table(config.bonds_path.as_deref().map(std::path::Path::new))?;
+    config.validate()?;
+
+    if let Some(frames) = args.headless_frames {
+        return headless::run_headless(frames, config);
+    }
 
-fn main() {
     App::new()
-        .add_plugins(DefaultPlugins.set(TaskPoolPlugin {
-            task_pool_options: TaskPoolOptions {
-                compute: TaskPoolThreadAssignmentPolicy {
-                    // set the minimum # of compute threads
-                    // to the total number of available threads
-                    min_threads: available_parallelism(),
-                    max_threads: usize::MAX, // unlimited max threads
-                    percent: 1.0,            // this value is irrelevant in this case
-                },
-                // keep the defaults for everything else
-                ..default()
-            },
-        }))
-        .add_systems(Startup, (setup, bounding_box::spawn_bounding_box))
+        .add_plugins((
+            DefaultPlugins.set(task_pool_plugin()),
+            SimulationPlugin { config },
+        ))
+        .init_resource::<ui_handler::CensusHistory>()
+        .init_gizmo_group::<ui_handler::CensusChartGizmos>()
+        .add_systems(
+            Startup,
+            (
+                setup,
+                ui_handler::configure_census_chart_gizmos,
+                camera_controller::spawn_camera,
+                bounding_box::spawn_bounding_box,
+            ),
+        )
         .add_systems(
             Update,
             (
-                particle_physics::handle_particles_physics,
                 ui_handler::update_ui,
+                ui_handler::switch_integrator,
+                ui_handler::switch_thermostat,
+                ui_handler::save_energy_log,
+                (
+                    ui_handler::record_census,
+                    ui_handler::update_census_ui,
+                    ui_handler::draw_census_chart,
+                )
+                    .chain(),
+                (
+                    camera_controller::select_followed_particle,
+                    camera_controller::reset_camera,
+                    camera_controller::zoom_camera,
+                    camera_controller::pan_camera,
+                    camera_controller::follow_particle,
+                )
+                    .chain(),
                 // debug_input_update,
+                particles_visuals::attach_particles_visuals,
                 particles_visuals::update_particles_visuals,
             ),
         )
+        // the chart node has its place once the ui layout is done
+        .add_systems(
+            PostUpdate,
+            ui_handler::place_census_chart_camera.after(TransformSystem::TransformPropagate),
+        )
         .run();
+    Ok(())
+}
+pub(crate) fn task_pool_plugin() -> TaskPoolPlugin {
+    TaskPoolPlugin {
+        task_pool_options: TaskPoolOptions {
+            compute: TaskPoolThreadAssignmentPolicy {
+                // set the minimum # of compute threads
+                // to the total number of available threads
+                min_threads: available_parallelism(),
+                max_threads: usize::MAX, // unlimited max threads
+                percent: 1.0,            // this value is irrelevant in this case
+            },
+            // keep the defaults for everything else
+            ..default()
+        },
+    }
 }
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2d);
+fn setup(mut commands: Commands) {
     // commands.spawn(DebugPointer {
     //     pos: Vec2::new(1f32, 1f32),
     // });
     //
     ui_handler::setup_ui(&mut commands);
-    particles_spawning::handle_spawning_particles(&mut commands, &asset_server);
 }
 // const USE_DEBUG_POINTER: bool = false;
 // const DEBUG_POINTER_MOVEMENT_SPEED: f32 = 30f32;
@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+
+use crate::{element::elements, particle::Particle};
+
+pub struct Molecule {
+    // indexes into `Simulation::particles`, sorted
+    pub particles: Vec<usize>,
+    pub formula: String,
+}
+
+// atoms grouped by bonds, single atoms count as molecules too
+#[derive(Default)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct Molecules {
+    // index into `molecules` for every particle
+    pub molecule_ids: Vec<usize>,
+    pub molecules: Vec<Molecule>,
+}
+impl Molecules {
+    pub fn molecule_of(&self, particle_index: usize) -> Option<&Molecule> {
+        let molecule_id = *self.molecule_ids.get(particle_index)?;
+        self.molecules.get(molecule_id)
+    }
+    // how many molecules there are of every formula
+    pub fn census(&self) -> BTreeMap<&str, usize> {
+        let mut census = BTreeMap::new();
+        for molecule in &self.molecules {
+            *census.entry(molecule.formula.as_str()).or_insert(0) += 1;
+        }
+        census
+    }
+}
+
+// connected components of the bond graph
+pub fn detect_molecules(particles: &[Particle]) -> Molecules {
+    let mut parents: Vec<usize> = (0..particles.len()).collect();
+    for particle in particles {
+        for (&target_index, _) in particle.bonds.range(particle.index + 1..) {
+            let root = find_root(&mut parents, particle.index);
+            let target_root = find_root(&mut parents, target_index);
+            // the smaller index is the root so ids don't depend on the order of bonds
+            parents[root.max(target_root)] = root.min(target_root);
+        }
+    }
+
+    let mut molecule_ids = vec![usize::MAX; particles.len()];
+    let mut molecules_particles: Vec<Vec<usize>> = Vec::new();
+    for index in 0..particles.len() {
+        let root = find_root(&mut parents, index);
+        if molecule_ids[root] == usize::MAX {
+            molecule_ids[root] = molecules_particles.len();
+            molecules_particles.push(Vec::new());
+        }
+        molecule_ids[index] = molecule_ids[root];
+        molecules_particles[molecule_ids[index]].push(index);
+    }
+
+    let molecules = molecules_particles
+        .into_iter()
+        .map(|molecule_particles| Molecule {
+            formula: hill_formula(
+                molecule_particles
+                    .iter()
+                    .map(|&index| particles[index].element_index),
+            ),
+            particles: molecule_particles,
+        })
+        .collect();
+    Molecules {
+        molecule_ids,
+        molecules,
+    }
+}
+fn find_root(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+        // path halving keeps the trees flat
+        parents[index] = parents[parents[index]];
+        index = parents[index];
+    }
+    index
+}
+
+// Hill notation: carbon, then hydrogen, then the rest alphabetically, without carbon everything is
+// alphabetical, like CH4, C2H6O, H2O or ClNa
+pub fn hill_formula(element_indexes: impl Iterator<Item = u8>) -> String {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for element_index in element_indexes {
+        *counts
+            .entry(elements()[element_index as usize].symbol.as_str())
+            .or_insert(0) += 1;
+    }
+
+    let mut formula = String::new();
+    let mut push = |symbol: &str, count: usize| {
+        formula.push_str(symbol);
+        if count > 1 {
+            formula.push_str(&count.to_string());
+        }
+    };
+    let carbon = counts.remove("C");
+    if let Some(carbon) = carbon {
+        push("C", carbon);
+        if let Some(hydrogen) = counts.remove("H") {
+            push("H", hydrogen);
+        }
+    }
+    for (symbol, count) in counts {
+        push(symbol, count);
+    }
+    formula
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_math::Vec2;
+
+    use super::*;
+    use crate::{
+        element::find_element_index,
+        particle::{Bond, BondType},
+    };
+
+    fn element_indexes(symbols: &[&str]) -> Vec<u8> {
+        symbols
+            .iter()
+            .map(|symbol| find_element_index(symbol).unwrap() as u8)
+            .collect()
+    }
+    fn particles(symbols: &[&str]) -> Vec<Particle> {
+        element_indexes(symbols)
+            .into_iter()
+            .enumerate()
+            .map(|(index, element_index)| {
+                Particle::new(
+                    Vec2::ZERO,
+                    index,
+                    element_index,
+                    &elements()[element_index as usize],
+                    Vec2::new(index as f32 * 100f32, 0f32),
+                )
+            })
+            .collect()
+    }
+    fn bond(particles: &mut [Particle], index: usize, other_index: usize) {
+        for (from, to) in [(index, other_index), (other_index, index)] {
+            let bond = Bond {
+                bond_type: BondType::Covalent,
+                electrons_used: 1,
+                bonded_pos: particles[to].position_pm,
+                bonded_element_index: particles[to].element_index,
+            };
+            particles[from].bonds.insert(to, bond);
+        }
+    }
+
+    #[test]
+    fn hill_formula_orders_carbon_hydrogen_then_alphabetically() {
+        for (symbols, formula) in [
+            (&["H", "O", "H"][..], "H2O"),
+            (&["H", "H", "C", "H", "H"][..], "CH4"),
+            (&["Na", "Cl"][..], "ClNa"),
+            (&["O"][..], "O"),
+        ] {
+            assert_eq!(hill_formula(element_indexes(symbols).into_iter()), formula);
+        }
+    }
+
+    #[test]
+    fn bonded_atoms_share_a_molecule() {
+        let mut particles = particles(&["H", "O", "H", "Na", "Cl", "O"]);
+        bond(&mut particles, 0, 1);
+        bond(&mut particles, 1, 2);
+        bond(&mut particles, 3, 4);
+        let molecules = detect_molecules(&particles);
+
+        let ids = &molecules.molecule_ids;
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(ids[1], ids[2]);
+        assert_eq!(ids[3], ids[4]);
+        assert_ne!(ids[0], ids[3]);
+        assert_ne!(ids[5], ids[0]);
+        assert_ne!(ids[5], ids[3]);
+        assert_eq!(molecules.molecule_of(0).unwrap().particles, [0, 1, 2]);
+        assert_eq!(molecules.molecule_of(0).unwrap().formula, "H2O");
+        assert_eq!(
+            molecules.census(),
+            BTreeMap::from([("ClNa", 1), ("H2O", 1), ("O", 1)])
+        );
+    }
+}
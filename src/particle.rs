@@ -1,100 +1,184 @@
-use std::usize;
+use std::collections::BTreeMap;
 
-use bevy::{math::bool, prelude::*, utils::HashMap};
+use bevy_math::Vec2;
 
-use crate::element::{ELEMENTS, Element};
-#[derive(Clone)]
+use crate::{
+    bond_table,
+    config::SimulationConfig,
+    electrostatics,
+    element::{Element, elements},
+};
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum BondType {
     Covalent,
-    Ionic, // for later
+    Ionic,
+}
+impl BondType {
+    // electrons are moved to the more electronegative element when the difference is big enough
+    pub fn between(
+        element: &Element,
+        other_element: &Element,
+        ionic_electronegativity_difference: f32,
+    ) -> BondType {
+        match (element.electronegativity, other_element.electronegativity) {
+            (Some(electronegativity), Some(other_electronegativity))
+                if (electronegativity - other_electronegativity).abs()
+                    > ionic_electronegativity_difference =>
+            {
+                BondType::Ionic
+            }
+            _ => BondType::Covalent,
+        }
+    }
 }
 #[derive(Clone)]
 pub struct Bond {
     pub bond_type: BondType,
     pub electrons_used: u8,
-    pub should_break: bool,
     pub bonded_pos: Vec2, // only for connection Gizmos
+    pub bonded_element_index: u8,
 }
-#[derive(Component)]
-pub(crate) struct Particle {
+#[derive(Clone)]
+pub struct Particle {
     pub element_index: u8,
     pub velocity_pm_ns: Vec2,      // pm / ns -> pico meters / nano seconds
     pub last_velocity_pm_ns: Vec2, // pm / ns -> pico meters / nano seconds
     pub index: usize,
-    pub bonds: HashMap<usize, Bond>,
+    // sorted by particle index so bonds are always resolved in the same order
+    pub bonds: BTreeMap<usize, Bond>,
     pub position_pm: Vec2, //pico meters
     // for debuging
     pub particles_in_range: Vec<Vec2>,
 
     pub connected_electrons_needed: u8,
+    // e, electrons given away (+) or taken (-) in ionic bonds
+    pub formal_charge: i8,
+    // e, shared electrons pulled towards the more electronegative side of covalent bonds
+    pub partial_charge: f32,
+    // u * pm / ns^2, sum of all forces from the last force calculation
+    pub force: Vec2,
+    // u * pm^2 / ns^2, kinetic energy the walls took (negative) in the last step
+    pub wall_work: f32,
 }
 #[derive(Clone)]
 pub struct LookupParticle {
     pub element_index: u8,
-    pub current_unused_valence_electrons: u8,
-    pub bonds_particle_index: HashMap<usize, Bond>,
+    pub bonds_particle_index: BTreeMap<usize, Bond>,
     pub connected_electrons_needed: u8,
+    pub formal_charge: i8,
+    pub partial_charge: f32,
 
     pub position_pm: Vec2, //pico meters
 }
 impl Particle {
     pub fn new(
-        velocity_ms: Vec2,
+        velocity_pm_ns: Vec2,
         index: usize,
         element_index: u8,
         element: &Element,
         position_pm: Vec2,
     ) -> Particle {
         Particle {
-            bonds: HashMap::new(),
+            bonds: BTreeMap::new(),
             element_index,
-            velocity_pm_ns: velocity_ms,
+            velocity_pm_ns,
             last_velocity_pm_ns: Vec2::ZERO,
             index,
             position_pm,
             connected_electrons_needed: element.connected_electrons_needed,
             particles_in_range: Vec::new(),
+            formal_charge: 0,
+            partial_charge: 0f32,
+            force: Vec2::ZERO,
+            wall_work: 0f32,
         }
     }
-    pub fn element(&self) -> &Element {
-        &ELEMENTS[self.element_index as usize]
+    pub fn element(&self) -> &'static Element {
+        &elements()[self.element_index as usize]
     }
-    pub fn break_bond(&mut self, bonds_map_key: &usize, bond: &Bond) {
-        self.bonds.remove(bonds_map_key);
-        self.connected_electrons_needed += bond.electrons_used;
+    // pm / ns^2
+    pub fn acceleration(&self) -> Vec2 {
+        self.force / self.element().mass_u
     }
-
-    // this is the same thing but just slower
-    pub fn break_bond_find_bond(&mut self, bonds_map_key: &usize) {
-        let bond = &self.bonds[bonds_map_key];
+    // e
+    pub fn charge(&self) -> f32 {
+        self.formal_charge as f32 + self.partial_charge
+    }
+    pub fn update_partial_charge(&mut self, config: &SimulationConfig) {
+        let Some(electronegativity) = self.element().electronegativity else {
+            self.partial_charge = 0f32;
+            return;
+        };
+        self.partial_charge = self
+            .bonds
+            .values()
+            .filter(|bond| bond.bond_type == BondType::Covalent)
+            .filter_map(|bond| {
+                let bonded_electronegativity =
+                    elements()[bond.bonded_element_index as usize].electronegativity?;
+                Some(
+                    (bonded_electronegativity - electronegativity)
+                        * bond.electrons_used as f32
+                        * config.partial_charge_per_electronegativity,
+                )
+            })
+            .sum();
+    }
+    pub fn break_bond(&mut self, bonds_map_key: &usize) {
+        let Some(bond) = self.bonds.remove(bonds_map_key) else {
+            return;
+        };
         self.connected_electrons_needed += bond.electrons_used;
-
-        self.bonds.remove(bonds_map_key);
+        self.formal_charge -= self.ionic_charge_change(&bond);
     }
+    // only one side of the bond, `reactions` registers both of them
     pub fn register_bond(
         &mut self,
         paritcle_index: usize,
         electrons_connected: u8,
-        should_break: bool,
-        target_particle: &LookupParticle,
+        bond_type: BondType,
+        bonded_element_index: u8,
+        bonded_pos: Vec2,
     ) {
+        let bond = Bond {
+            bond_type,
+            electrons_used: electrons_connected,
+            bonded_pos,
+            bonded_element_index,
+        };
         self.connected_electrons_needed -= electrons_connected;
-        self.bonds.insert(
-            paritcle_index,
-            Bond {
-                bond_type: BondType::Covalent,
-                electrons_used: electrons_connected,
-                should_break,
-                bonded_pos: target_particle.position_pm,
-            },
-        );
+        self.formal_charge += self.ionic_charge_change(&bond);
+        self.bonds.insert(paritcle_index, bond);
     }
-    pub fn break_all_out_of_range_bonds(&mut self) {
-        for bond in self.bonds.to_owned() {
-            if bond.1.bonded_pos.distance(self.position_pm) > crate::particle_physics::BOND_DISTANCE
-            {
-                self.break_bond(&bond.0, &bond.1);
-            }
+    // the less electronegative side of an ionic bond gives its electrons away
+    fn ionic_charge_change(&self, bond: &Bond) -> i8 {
+        if bond.bond_type != BondType::Ionic {
+            return 0;
+        }
+        let bonded_element = &elements()[bond.bonded_element_index as usize];
+        if self.element().electronegativity < bonded_element.electronegativity {
+            bond.electrons_used as i8
+        } else {
+            -(bond.electrons_used as i8)
+        }
+    }
+}
+// bonds form up to this distance
+pub fn bond_forming_distance(
+    element: &Element,
+    other_element: &Element,
+    bond_type: BondType,
+    electrons_used: u8,
+    config: &SimulationConfig,
+) -> f32 {
+    match bond_type {
+        BondType::Covalent => {
+            bond_table::bond_parameters(element, other_element, electrons_used, config).length_pm
+                * config.covalent_bond_forming_factor
+        }
+        BondType::Ionic => {
+            electrostatics::ionic_contact_distance(element, other_element)
+                * config.ionic_bond_forming_factor
         }
     }
 }
@@ -1,109 +1,296 @@
-use std::{usize, vec};
+use std::sync::atomic::{AtomicU32, Ordering};
 
-use bevy::{
-    math::{Vec2, vec2},
-    prelude::*,
-    tasks::ParallelSlice,
-};
+use bevy_math::{UVec2, Vec2};
+use bevy_tasks::{ComputeTaskPool, ParallelSlice, ParallelSliceMut, TaskPool};
 
-use crate::{
-    bounding_box::BOX_BOUNDS_SIZE_PIXELS,
-    particles_spawning::{self, PARTICLES_COUNT},
-};
-pub const SMOOTHING_DISTANCE: u32 = 40;
-pub fn split_particles_into_grid(particles: &[Vec2]) -> Vec<Vec<usize>> {
-    let mut output: Vec<Vec<usize>> = vec![Vec::new(); TOTAL_GRID_SIZE];
+use crate::config::SimulationConfig;
 
-    // this parallel?
-    for i in 0..particles_spawning::PARTICLES_COUNT as usize {
-        let _particle = particles[i];
-        let grid_index = pos_to_grid_index(&_particle);
-        if grid_index == usize::MAX || grid_index > TOTAL_GRID_SIZE {
-            continue;
-        }
-        output[grid_index].push(i);
-    }
+// a zero cutoff or box would ask for endless cells, `validate` rejects them but the grid shouldn't
+// depend on it
+const MIN_CELL_SIZE_PM: f32 = 1f32;
+// bigger boxes get cells bigger than the cutoff, which keeps the counters of every chunk small
+const MAX_CELLS_PER_SIDE: f32 = 1024f32;
+// fewer items than this per thread aren't worth splitting
+const MIN_ITEMS_PER_CHUNK: usize = 1024;
 
-    output
-}
-pub fn pixel_pos_to_gird_pos(pixel_pos: &Vec2) -> Vec2 {
-    let raw = pixel_pos / SMOOTHING_DISTANCE as f32 + vec2(GRID_SIZE_X / 2f32, GRID_SIZE_Y / 2f32);
-    vec2((raw.x as usize) as f32, (raw.y as usize) as f32)
+// splits the box into cells at least as big as the interaction cutoff, so every particle closer
+// than the cutoff is in the same or one of the 8 neighbouring cells. The box is centered on the
+// origin, particles that left it are kept in the closest edge cell instead of being dropped.
+// Cells are a counting sort of the particle indexes: `sorted_indexes` holds the particles of
+// cell `c` at `cell_starts[c]..cell_starts[c + 1]`, so a rebuild only reuses a few flat buffers
+#[derive(Debug)]
+pub struct SpatialGrid {
+    box_size_pm: Vec2,
+    cutoff_pm: f32,
+    // number of cells in x and y
+    size: UVec2,
+    cell_size_pm: Vec2,
+    // cell (x, y) is number `y * size.x + x`, one more start than cells closes the last one
+    cell_starts: Vec<u32>,
+    // atomic so the chunks of a rebuild can fill their slots at the same time, every slot is
+    // written by one chunk only
+    sorted_indexes: Vec<AtomicU32>,
+    // cell of every item from the last rebuild, kept so its allocation is reused
+    item_cells: Vec<u32>,
+    // items every chunk has in every cell, cell by cell so all chunks of a cell are next to each
+    // other, the prefix sum turns them into the next free slot of the chunk in the cell
+    chunk_slots: Vec<AtomicU32>,
+    out_of_bounds: Vec<usize>,
 }
-pub fn pos_to_grid_index(pixel_pos: &Vec2) -> usize {
-    grid_pos_to_index(&pixel_pos_to_gird_pos(pixel_pos))
+impl Clone for SpatialGrid {
+    fn clone(&self) -> SpatialGrid {
+        SpatialGrid {
+            box_size_pm: self.box_size_pm,
+            cutoff_pm: self.cutoff_pm,
+            size: self.size,
+            cell_size_pm: self.cell_size_pm,
+            cell_starts: self.cell_starts.clone(),
+            sorted_indexes: self
+                .sorted_indexes
+                .iter()
+                .map(|index| AtomicU32::new(index.load(Ordering::Relaxed)))
+                .collect(),
+            item_cells: self.item_cells.clone(),
+            // only used during a rebuild
+            chunk_slots: Vec::new(),
+            out_of_bounds: self.out_of_bounds.clone(),
+        }
+    }
 }
-pub const GRID_SIZE_X: f32 = (BOX_BOUNDS_SIZE_PIXELS.x as u32).div_ceil(SMOOTHING_DISTANCE) as f32;
-pub const GRID_SIZE_Y: f32 = (BOX_BOUNDS_SIZE_PIXELS.y as u32).div_ceil(SMOOTHING_DISTANCE) as f32;
-pub const TOTAL_GRID_SIZE: usize = (GRID_SIZE_X as usize) * (GRID_SIZE_Y as usize + 1) + 1;
+impl SpatialGrid {
+    pub fn new(box_size_pm: Vec2, cutoff_pm: f32) -> SpatialGrid {
+        let size = (box_size_pm / cutoff_pm.max(MIN_CELL_SIZE_PM))
+            .floor()
+            .clamp(Vec2::ONE, Vec2::splat(MAX_CELLS_PER_SIDE))
+            .as_uvec2()
+            .max(UVec2::ONE);
+        SpatialGrid {
+            box_size_pm,
+            cutoff_pm,
+            size,
+            cell_size_pm: box_size_pm / size.as_vec2(),
+            cell_starts: vec![0; (size.x * size.y) as usize + 1],
+            sorted_indexes: Vec::new(),
+            item_cells: Vec::new(),
+            chunk_slots: Vec::new(),
+            out_of_bounds: Vec::new(),
+        }
+    }
+    pub fn from_config(config: &SimulationConfig) -> SpatialGrid {
+        SpatialGrid::new(config.box_size_pm, config.smoothing_distance.pm())
+    }
+    // sorts `items` into cells by `position_pm`, the cells are resized first when the box or the
+    // cutoff in `config` changed since the last rebuild. Every step runs in parallel: the items
+    // are split into chunks that count their items per cell, a prefix sum over the counts gives
+    // every chunk its own slots in every cell and the chunks fill them. Items keep their order
+    // inside a cell, so the result doesn't depend on the number of threads
+    pub fn rebuild<T: Sync>(
+        &mut self,
+        items: &[T],
+        position_pm: impl Fn(&T) -> Vec2 + Send + Sync,
+        config: &SimulationConfig,
+    ) {
+        if self.box_size_pm != config.box_size_pm
+            || self.cutoff_pm != config.smoothing_distance.pm()
+        {
+            *self = SpatialGrid::from_config(config);
+        }
 
-pub fn grid_pos_to_index(grid_pos: &Vec2) -> usize {
-    ((grid_pos.y) * GRID_SIZE_X + grid_pos.x) as usize
-}
-pub fn get_connected_cells(sample_grid_pos: &Vec2) -> Vec<Vec2> {
-    let mut output = Vec::with_capacity(9);
+        let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+        let cells = self.cell_starts.len() - 1;
+        let chunks = task_pool
+            .thread_num()
+            .min(items.len().div_ceil(MIN_ITEMS_PER_CHUNK))
+            .max(1);
+        let chunk_size = items.len().div_ceil(chunks).max(1);
+        self.item_cells.resize(items.len(), 0);
+        self.sorted_indexes
+            .resize_with(items.len(), AtomicU32::default);
+        self.chunk_slots
+            .resize_with(cells * chunks, AtomicU32::default);
+        self.chunk_slots
+            .par_splat_map_mut(task_pool, None, |_, slots| {
+                slots.iter_mut().for_each(|slot| *slot.get_mut() = 0);
+            });
 
-    if sample_grid_pos.y < 0f32 || sample_grid_pos.y > GRID_SIZE_Y {
-        return output;
-    }
+        // cell of every item and how many items every chunk has in every cell
+        let mut item_cells = std::mem::take(&mut self.item_cells);
+        let grid = &*self;
+        let chunks_out_of_bounds =
+            item_cells.par_chunk_map_mut(task_pool, chunk_size, |chunk_index, chunk| {
+                let first_item = chunk_index * chunk_size;
+                let mut out_of_bounds = Vec::new();
+                for (offset, (item, cell)) in items[first_item..].iter().zip(chunk).enumerate() {
+                    let position_pm = position_pm(item);
+                    *cell = grid.cell_index(grid.cell_position(position_pm));
+                    if !grid.contains(position_pm) {
+                        out_of_bounds.push(first_item + offset);
+                    }
+                    grid.chunk_slots[*cell as usize * chunks + chunk_index]
+                        .fetch_add(1, Ordering::Relaxed);
+                }
+                out_of_bounds
+            });
+        self.item_cells = item_cells;
+        self.out_of_bounds.clear();
+        self.out_of_bounds
+            .extend(chunks_out_of_bounds.into_iter().flatten());
 
-    if sample_grid_pos.y < GRID_SIZE_Y {
-        if sample_grid_pos.x > 0f32 {
-            output.push(sample_grid_pos + vec2(-1f32, 1f32));
-        }
-        output.push(sample_grid_pos + vec2(0f32, 1f32));
-        if sample_grid_pos.x < GRID_SIZE_X {
-            output.push(sample_grid_pos + vec2(1f32, 1f32));
-        }
-    }
+        // exclusive prefix sum over the counts, first the sum of every band of cells, then every
+        // band continues from the bands before it
+        let cells_per_band = cells.div_ceil(chunks).max(1);
+        let band_sums =
+            self.chunk_slots
+                .par_chunk_map(task_pool, cells_per_band * chunks, |_, band| {
+                    band.iter()
+                        .map(|slot| slot.load(Ordering::Relaxed))
+                        .sum::<u32>()
+                });
+        let band_starts: Vec<u32> = band_sums
+            .iter()
+            .scan(0, |next, sum| {
+                let start = *next;
+                *next += sum;
+                Some(start)
+            })
+            .collect();
+        let chunk_slots = &self.chunk_slots;
+        // the last start is the item count, it isn't part of any band
+        let mut band_cell_starts = &mut self.cell_starts[..cells];
+        band_cell_starts.par_chunk_map_mut(task_pool, cells_per_band, |band_index, cell_starts| {
+            let mut next = band_starts[band_index];
+            let first_cell = band_index * cells_per_band;
+            for (offset, cell_start) in cell_starts.iter_mut().enumerate() {
+                *cell_start = next;
+                let cell = first_cell + offset;
+                for slot in &chunk_slots[cell * chunks..(cell + 1) * chunks] {
+                    let count = slot.load(Ordering::Relaxed);
+                    slot.store(next, Ordering::Relaxed);
+                    next += count;
+                }
+            }
+        });
+        self.cell_starts[cells] = items.len() as u32;
 
-    if sample_grid_pos.x > 0f32 {
-        output.push(sample_grid_pos + vec2(-1f32, 0f32));
+        // every chunk fills its own slots
+        let sorted_indexes = &self.sorted_indexes;
+        self.item_cells
+            .par_chunk_map(task_pool, chunk_size, |chunk_index, chunk| {
+                let first_item = chunk_index * chunk_size;
+                for (offset, &cell) in chunk.iter().enumerate() {
+                    let slot = chunk_slots[cell as usize * chunks + chunk_index]
+                        .fetch_add(1, Ordering::Relaxed);
+                    sorted_indexes[slot as usize]
+                        .store((first_item + offset) as u32, Ordering::Relaxed);
+                }
+            });
     }
-    output.push(sample_grid_pos + vec2(0f32, 0f32));
-    if sample_grid_pos.x < GRID_SIZE_X {
-        output.push(sample_grid_pos + vec2(1f32, 0f32));
+    // indexes of particles that were outside of the box at the last rebuild
+    pub fn out_of_bounds(&self) -> &[usize] {
+        &self.out_of_bounds
     }
-    if sample_grid_pos.y > 0f32 {
-        if sample_grid_pos.x > 0f32 {
-            output.push(sample_grid_pos + vec2(-1f32, -1f32));
-        }
-        output.push(sample_grid_pos + vec2(0f32, -1f32));
-        if sample_grid_pos.x < GRID_SIZE_X {
-            output.push(sample_grid_pos + vec2(1f32, -1f32));
-        }
+    pub fn size(&self) -> UVec2 {
+        self.size
     }
-    output
-}
-pub fn get_connected_cells_indexes(sample_grid_pos: &Vec2) -> Vec<usize> {
-    let mut output: Vec<usize> = Vec::with_capacity(9);
-    for pos in get_connected_cells(sample_grid_pos) {
-        let index = grid_pos_to_index(&pos);
-
-        output.push(index);
+    pub fn contains(&self, position_pm: Vec2) -> bool {
+        let half_box_size = self.box_size_pm / 2f32;
+        // NaN positions are outside as well
+        position_pm.x >= -half_box_size.x
+            && position_pm.x <= half_box_size.x
+            && position_pm.y >= -half_box_size.y
+            && position_pm.y <= half_box_size.y
+    }
+    // cell containing `position_pm`, or the closest one for positions outside of the box
+    pub fn cell_position(&self, position_pm: Vec2) -> UVec2 {
+        let raw = (position_pm + self.box_size_pm / 2f32) / self.cell_size_pm;
+        // NaN and negative values turn into 0
+        raw.as_uvec2().min(self.size - UVec2::ONE)
+    }
+    fn cell_index(&self, cell_position: UVec2) -> u32 {
+        cell_position.y * self.size.x + cell_position.x
+    }
+    // every particle in the cell of `position_pm` and the cells around it, including the
+    // particle at `position_pm` itself. The cells of one row are next to each other in
+    // `sorted_indexes`, so this walks at most 3 slices and doesn't allocate
+    pub fn neighbours(&self, position_pm: Vec2) -> impl Iterator<Item = usize> + '_ {
+        let center = self.cell_position(position_pm);
+        let min = center.saturating_sub(UVec2::ONE);
+        let max = (center + UVec2::ONE).min(self.size - UVec2::ONE);
+        (min.y..=max.y).flat_map(move |y| {
+            let start = self.cell_starts[self.cell_index(UVec2::new(min.x, y)) as usize];
+            let end = self.cell_starts[self.cell_index(UVec2::new(max.x, y)) as usize + 1];
+            self.sorted_indexes[start as usize..end as usize]
+                .iter()
+                .map(|index| index.load(Ordering::Relaxed) as usize)
+        })
     }
-    output
 }
-pub fn calculate_connected_cells_for_every_particle(particle_positions: &[Vec2]) -> Vec<usize> {
-    // array of vectors for particles that can be indexed by particle index to aces connected cells
-    // so i don't have to calculate them multiple times
-    // TODO: test if parallel could work
-    let data_chunks =
-        particle_positions.par_splat_map(bevy::tasks::ComputeTaskPool::get(), None, |_, data| {
-            let mut output_chunk = Vec::new();
 
-            for sample_point in data {
-                output_chunk.append(&mut get_connected_cells_indexes(&pixel_pos_to_gird_pos(
-                    sample_point,
-                )));
-            }
-            output_chunk
-        });
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
 
-    let mut connected_cells: Vec<usize> = Vec::with_capacity((PARTICLES_COUNT * 9) as usize);
-    for mut data in data_chunks {
-        connected_cells.append(&mut data);
+    use super::*;
+    use crate::units::Length;
+
+    #[test]
+    fn degenerate_sizes_give_at_least_one_cell() {
+        for (box_size_pm, cutoff_pm) in [
+            (Vec2::new(1000f32, 1000f32), 0f32),
+            (Vec2::new(1000f32, 1000f32), -5f32),
+            (Vec2::ZERO, 800f32),
+            (Vec2::new(-100f32, 1000f32), 800f32),
+            (Vec2::splat(f32::NAN), f32::NAN),
+        ] {
+            let grid = SpatialGrid::new(box_size_pm, cutoff_pm);
+            assert!(grid.size().x >= 1 && grid.size().y >= 1);
+            assert!(grid.size().x as f32 <= MAX_CELLS_PER_SIDE);
+        }
     }
 
-    connected_cells
+    #[test]
+    fn neighbours_match_brute_force() {
+        // enough particles for several chunks, a part of them outside of the box
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let positions: Vec<Vec2> = (0..5000)
+            .map(|_| {
+                Vec2::new(
+                    rng.random_range(-3500f32..3500f32),
+                    rng.random_range(-2000f32..2000f32),
+                )
+            })
+            .collect();
+        let mut grid = SpatialGrid::new(Vec2::ZERO, 1f32);
+
+        // the second cutoff makes the rebuild resize the grid
+        for cutoff_pm in [400f32, 650f32] {
+            let config = SimulationConfig {
+                box_size_pm: Vec2::new(5000f32, 3000f32),
+                smoothing_distance: Length::from_pm(cutoff_pm),
+                ..Default::default()
+            };
+            grid.rebuild(&positions, |&position_pm| position_pm, &config);
+
+            let expected_out_of_bounds: Vec<usize> = (0..positions.len())
+                .filter(|&index| {
+                    positions[index].x.abs() > 2500f32 || positions[index].y.abs() > 1500f32
+                })
+                .collect();
+            assert!(!expected_out_of_bounds.is_empty());
+            assert_eq!(grid.out_of_bounds(), expected_out_of_bounds);
+
+            for &position_pm in &positions {
+                let mut found: Vec<usize> = grid
+                    .neighbours(position_pm)
+                    .filter(|&index| positions[index].distance(position_pm) < cutoff_pm)
+                    .collect();
+                found.sort_unstable();
+                let expected: Vec<usize> = (0..positions.len())
+                    .filter(|&index| positions[index].distance(position_pm) < cutoff_pm)
+                    .collect();
+                assert_eq!(found, expected);
+            }
+        }
+    }
 }
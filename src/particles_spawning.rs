@@ -1,106 +1,112 @@
 use crate::{
-    bounding_box::BOX_BOUNDS_SIZE_PIXELS,
+    config::SimulationConfig,
     element::{self},
     particle::Particle,
+    random::SimulationRng,
+    thermostat::standard_normal,
+    units::{Mass, Temperature},
 };
-use bevy::{math::vec2, prelude::*, text::TextBounds};
-use rand::{Rng, rngs::ThreadRng};
+use bevy_math::{Vec2, vec2};
+use rand::Rng;
+use rand_chacha::ChaCha8Rng;
 
-const CIRCLE_SPRITE_PATH: &str = "sprites/circle.png";
 pub const PARTICLE_RAY: f32 = 0.25f32;
 pub const PARTICLE_RESOLUTION: f32 = 50f32;
-pub const STANDARD_PARTICLE_MASS: f32 = 2f32;
 
-pub const ELEMENTS_TO_SPAWN: [usize; 2] = [0, 1];
+pub fn spawn_particles(
+    config: &SimulationConfig,
+    simulation_rng: &mut SimulationRng,
+) -> Vec<Particle> {
+    let rng = simulation_rng.rng();
+    let elements_to_spawn: Vec<usize> = config
+        .elements_to_spawn
+        .iter()
+        .map(|symbol| {
+            element::find_element_index(symbol).unwrap_or_else(|| {
+                panic!("element `{symbol}` to spawn is not in the element table")
+            })
+        })
+        .collect();
 
-pub fn handle_spawning_particles(commands: &mut Commands, asset_server: &Res<AssetServer>) {
-    let mut rng = rand::rng();
-
-    for i in 0..PARTICLES_COUNT {
-        spawn_particle(
-            STANDARD_PARTICLE_MASS,
-            PARTICLE_RAY,
-            get_particle_spawn_position(i as f32, &mut rng),
-            i as usize,
-            commands,
-            asset_server,
-            &mut rng,
-        );
-    }
+    let mut particles: Vec<Particle> = (0..config.particles_count)
+        .map(|i| {
+            spawn_particle(
+                get_particle_spawn_position(i as f32, config),
+                i as usize,
+                &elements_to_spawn,
+                rng,
+                config,
+            )
+        })
+        .collect();
+    remove_center_of_mass_drift(&mut particles);
+    particles
 }
 fn spawn_particle(
-    mass: f32,
-    ray: f32,
     pos: Vec2,
     index: usize,
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-    rng: &mut ThreadRng,
-) {
-    let font_handle: Handle<Font> = asset_server.load("fonts/font.ttf");
-    let sprite = Sprite::from_image(asset_server.load(CIRCLE_SPRITE_PATH));
-
-    let transform = Transform {
-        translation: Vec3::new(pos.x, pos.y, 0f32),
-        scale: Vec3::new(ray, ray, ray),
-        ..default()
-    };
-    let (element_ref, element_index) = get_random_element_to_spawn(rng);
+    elements_to_spawn: &[usize],
+    rng: &mut ChaCha8Rng,
+    config: &SimulationConfig,
+) -> Particle {
+    let (element_ref, element_index) = get_random_element_to_spawn(elements_to_spawn, rng);
 
-    let particle = Particle::new(
-        vec2(1f32, 0f32),
+    Particle::new(
+        maxwell_boltzmann_velocity(
+            Mass::from_u(element_ref.mass_u),
+            config.initial_temperature_k,
+            rng,
+        ),
         index,
         element_index as u8,
         element_ref,
         pos,
-    );
-    commands.spawn((
-        particle,
-        transform,
-        sprite,
-        Text2d::new(" ".to_string() + element_ref.symbol),
-        TextLayout::new(JustifyText::Left, LineBreak::AnyCharacter),
-        TextColor(Color::Srgba(Srgba::RED)),
-        TextFont {
-            font: font_handle,
-            font_size: 60.0,
-            ..Default::default()
-        },
-        // Wrap text in the rectangle
-        TextBounds::new(1000.0, 1000.0),
-        // Ensure the text is drawn on top of the box
-    ));
+    )
 }
-
-fn get_random_element_to_spawn(rng: &mut ThreadRng) -> (&element::Element, usize) {
-    let element_index = ELEMENTS_TO_SPAWN[rng.random_range(..ELEMENTS_TO_SPAWN.len())];
-    (&element::ELEMENTS[element_index], element_index)
+// every velocity component is normally distributed with the variance of k_B * T / m
+fn maxwell_boltzmann_velocity(mass: Mass, temperature: Temperature, rng: &mut ChaCha8Rng) -> Vec2 {
+    vec2(standard_normal(rng), standard_normal(rng)) * temperature.thermal_speed(mass)
+}
+// so the whole system doesn't slowly fly into one of the walls
+fn remove_center_of_mass_drift(particles: &mut [Particle]) {
+    let total_mass: f32 = particles
+        .iter()
+        .map(|particle| particle.element().mass_u)
+        .sum();
+    if total_mass == 0f32 {
+        return;
+    }
+    let momentum: Vec2 = particles
+        .iter()
+        .map(|particle| particle.velocity_pm_ns * particle.element().mass_u)
+        .sum();
+    let drift = momentum / total_mass;
+    for particle in particles {
+        particle.velocity_pm_ns -= drift;
+    }
 }
 
-pub const PARTICLES_COUNT: u32 = 100;
-const PARTICLES_LAYERS: u32 = 10;
-const PARTICLES_SPACING: f32 = 30f32;
-
-const PARTICLES_SIZE_ASPECT: f32 = PARTICLES_COUNT as f32 / PARTICLES_LAYERS as f32;
-const OFFSET_VEC: Vec2 = vec2(
-    -PARTICLES_SPACING * PARTICLES_SIZE_ASPECT / 2f32,
-    -(PARTICLES_LAYERS as f32 / 2f32) * PARTICLES_SPACING,
-);
-
-fn get_particle_spawn_position(index: f32, rng: &mut ThreadRng) -> Vec2 {
-    get_box_spawn_point(index)
-    //get_random_spawn_point(rng)
+fn get_random_element_to_spawn(
+    elements_to_spawn: &[usize],
+    rng: &mut ChaCha8Rng,
+) -> (&'static element::Element, usize) {
+    let element_index = elements_to_spawn[rng.random_range(..elements_to_spawn.len())];
+    (&element::elements()[element_index], element_index)
 }
-fn get_random_spawn_point(rng: &mut ThreadRng) -> Vec2 {
-    let real_box_size = BOX_BOUNDS_SIZE_PIXELS / 2f32;
-    let y = rng.random_range(-(real_box_size.y) as i32..(real_box_size.y) as i32);
-    let x = rng.random_range(-(real_box_size.x) as i32..(real_box_size.x) as i32);
 
-    vec2(x as f32, y as f32)
+fn get_particle_spawn_position(index: f32, config: &SimulationConfig) -> Vec2 {
+    get_box_spawn_point(index, config)
 }
-fn get_box_spawn_point(index: f32) -> Vec2 {
-    let y = (index / PARTICLES_SIZE_ASPECT).floor();
-    let x = index - y * PARTICLES_SIZE_ASPECT;
+fn get_box_spawn_point(index: f32, config: &SimulationConfig) -> Vec2 {
+    let particles_size_aspect = config.particles_count as f32 / config.particles_layers as f32;
+    let spacing_pm = config.particles_spacing.pm();
+    let offset_vec = vec2(
+        -spacing_pm * particles_size_aspect / 2f32,
+        -(config.particles_layers as f32 / 2f32) * spacing_pm,
+    );
+
+    let y = (index / particles_size_aspect).floor();
+    let x = index - y * particles_size_aspect;
 
-    vec2(x, y) * PARTICLES_SPACING + OFFSET_VEC
+    vec2(x, y) * spacing_pm + offset_vec
 }
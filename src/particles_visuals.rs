@@ -1,16 +1,44 @@
-use bevy::{
-    color::palettes::css::{DARK_BLUE, LIGHT_GREEN},
-    math::{VectorSpace, vec3},
-    prelude::*,
-    sprite::Sprite,
-};
+use bevy::{math::vec3, prelude::*, sprite::Sprite, text::TextBounds};
 
-use crate::{particle::Particle, particles_spawning::PARTICLE_RAY};
+use chemical_simulation::{
+    particles_spawning::PARTICLE_RAY, simulation::Simulation, simulation_plugin::ParticleEntity,
+    units::pm_to_world,
+};
 const SHOW_PARTICLE_VISUALS: bool = true;
-const SPEED_VISUALIZATION_SCALE: f32 = 80f32;
+const CIRCLE_SPRITE_PATH: &str = "sprites/circle.png";
+const FONT_PATH: &str = "fonts/font.ttf";
+
+pub fn attach_particles_visuals(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    simulation: Res<Simulation>,
+    new_particles: Query<(Entity, &ParticleEntity), Added<ParticleEntity>>,
+) {
+    for (entity, particle_entity) in &new_particles {
+        let particle = &simulation.particles[particle_entity.index];
+        let font_handle: Handle<Font> = asset_server.load(FONT_PATH);
+        let sprite = Sprite::from_image(asset_server.load(CIRCLE_SPRITE_PATH));
+
+        commands.entity(entity).insert((
+            sprite,
+            Text2d::new(" ".to_string() + &particle.element().symbol),
+            TextLayout::new(JustifyText::Left, LineBreak::AnyCharacter),
+            TextColor(Color::Srgba(Srgba::RED)),
+            TextFont {
+                font: font_handle,
+                font_size: 60.0,
+                ..Default::default()
+            },
+            // Wrap text in the rectangle
+            TextBounds::new(1000.0, 1000.0),
+            // Ensure the text is drawn on top of the box
+        ));
+    }
+}
 
 pub fn update_particles_visuals(
-    mut particles: Query<(&mut Transform, &Particle, &mut Sprite, &mut Text2d)>,
+    mut particles: Query<(&mut Transform, &ParticleEntity, &mut Sprite, &mut Text2d)>,
+    simulation: Res<Simulation>,
     mut gizmos: Gizmos,
 ) {
     if !SHOW_PARTICLE_VISUALS {
@@ -19,13 +47,10 @@ pub fn update_particles_visuals(
 
     particles
         .iter_mut()
-        .for_each(|(mut transform, particle, mut sprite, mut text)| {
-            // let t = particle.bonds.len() as f32 / 2f32;
-
-            let t = particle.connected_electrons_needed as f32
-                / particle.element().connected_electrons_needed as f32;
-
-            sprite.color = Color::Srgba(Srgba::lerp(DARK_BLUE, LIGHT_GREEN, t));
+        .for_each(|(mut transform, particle_entity, mut sprite, mut text)| {
+            let particle = &simulation.particles[particle_entity.index];
+            let [red, green, blue] = particle.element().color;
+            sprite.color = Color::srgb_u8(red, green, blue);
             text.0 = format!(
                 "{}{}",
                 particle.element().symbol,
@@ -41,7 +66,11 @@ pub fn update_particles_visuals(
             // }
 
             for bond in &particle.bonds {
-                gizmos.line_2d(particle.position_pm, bond.1.bonded_pos, Srgba::BLACK);
+                gizmos.line_2d(
+                    pm_to_world(particle.position_pm),
+                    pm_to_world(bond.1.bonded_pos),
+                    Srgba::BLACK,
+                );
             }
         });
 }
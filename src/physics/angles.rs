@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use bevy_math::Vec2;
+
+use crate::{
+    element::Element,
+    particle::{Bond, BondType},
+};
+
+// lone pairs push bonds a bit closer together, H2O is 104.5 and NH3 is 107 degrees
+const LONE_PAIR_SQUEEZE_DEGREES: f32 = 2.5;
+
+// bonds that shape the molecule, ionic bonds don't
+pub fn is_angle_bond(bond: &Bond) -> bool {
+    bond.bond_type == BondType::Covalent
+}
+
+// VSEPR: bonded atoms and lone pairs of the central atom spread out as far as they can,
+// radians
+pub fn equilibrium_angle(
+    element: &Element,
+    bonds: &BTreeMap<usize, Bond>,
+    formal_charge: i8,
+) -> f32 {
+    let bonded_atoms = bonds.values().filter(|bond| is_angle_bond(bond)).count() as i32;
+    let shared_electrons: i32 = bonds
+        .values()
+        .filter(|bond| is_angle_bond(bond))
+        .map(|bond| bond.electrons_used as i32)
+        .sum();
+    let lone_pairs =
+        ((element.valence_electrons as i32 - shared_electrons - formal_charge as i32) / 2).max(0);
+
+    let angle_degrees = match bonded_atoms + lone_pairs {
+        0..=2 => 180f32,
+        3 => 120f32 - LONE_PAIR_SQUEEZE_DEGREES * lone_pairs as f32,
+        4 => 109.47f32 - LONE_PAIR_SQUEEZE_DEGREES * lone_pairs as f32,
+        // trigonal bipyramidal and octahedral, the right angles between neighbours
+        _ => 90f32,
+    };
+    angle_degrees.to_radians()
+}
+
+// harmonic angle term `stiffness / 2 * (angle - equilibrium)^2` between the bonds from
+// `center` to `end` and `center` to `other_end`, returns the force acting on `end`
+pub fn angle_force_on_end(
+    center_pm: Vec2,
+    end_pm: Vec2,
+    other_end_pm: Vec2,
+    equilibrium_angle: f32,
+    stiffness: f32,
+) -> Vec2 {
+    let to_end = end_pm - center_pm;
+    let to_other_end = other_end_pm - center_pm;
+    let end_distance = to_end.length();
+    if end_distance == 0f32 || to_other_end.length_squared() == 0f32 {
+        return Vec2::ZERO;
+    }
+    let end_direction = to_end / end_distance;
+    let other_end_direction = to_other_end.normalize();
+    let cos = end_direction.dot(other_end_direction).clamp(-1f32, 1f32);
+    let angle = cos.acos();
+
+    // moving `end` this way opens the angle
+    let opening_direction = (end_direction * cos - other_end_direction).normalize_or_zero();
+    -stiffness * (angle - equilibrium_angle) / end_distance * opening_direction
+}
+// potential of `angle_force_on_end`
+pub fn angle_energy(
+    center_pm: Vec2,
+    end_pm: Vec2,
+    other_end_pm: Vec2,
+    equilibrium_angle: f32,
+    stiffness: f32,
+) -> f32 {
+    let to_end = end_pm - center_pm;
+    let to_other_end = other_end_pm - center_pm;
+    if to_end.length_squared() == 0f32 || to_other_end.length_squared() == 0f32 {
+        return 0f32;
+    }
+    let cos = to_end
+        .normalize()
+        .dot(to_other_end.normalize())
+        .clamp(-1f32, 1f32);
+    let angle_change = cos.acos() - equilibrium_angle;
+    stiffness / 2f32 * angle_change * angle_change
+}
+// force on the central atom, keeps the momentum of the three atoms unchanged
+pub fn angle_force_on_center(
+    center_pm: Vec2,
+    end_pm: Vec2,
+    other_end_pm: Vec2,
+    equilibrium_angle: f32,
+    stiffness: f32,
+) -> Vec2 {
+    -angle_force_on_end(
+        center_pm,
+        end_pm,
+        other_end_pm,
+        equilibrium_angle,
+        stiffness,
+    ) - angle_force_on_end(
+        center_pm,
+        other_end_pm,
+        end_pm,
+        equilibrium_angle,
+        stiffness,
+    )
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bond_table::BondParameters;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BondPotential {
+    // spring around the bond length, the well has no edge so bonds only break by energy
+    Harmonic,
+    // anharmonic well as deep as the dissociation energy, flattens out when stretched
+    Morse,
+}
+
+// u * pm / ns^2 pulling the bonded atoms together, negative pushes them apart
+pub fn bond_force(parameters: &BondParameters, distance_pm: f32, potential: BondPotential) -> f32 {
+    let stretch = distance_pm - parameters.length_pm;
+    match potential {
+        BondPotential::Harmonic => parameters.stiffness * stretch,
+        BondPotential::Morse => {
+            let decay = (-parameters.morse_width * stretch).exp();
+            2f32 * parameters.dissociation_energy.u_pm2_ns2()
+                * parameters.morse_width
+                * decay
+                * (1f32 - decay)
+        }
+    }
+}
+// potential of `bond_force` in u * pm^2 / ns^2, minus the dissociation energy at the bond length,
+// the Morse well goes up to zero when the atoms are pulled apart
+pub fn bond_energy(parameters: &BondParameters, distance_pm: f32, potential: BondPotential) -> f32 {
+    let stretch = distance_pm - parameters.length_pm;
+    let depth = parameters.dissociation_energy.u_pm2_ns2();
+    let well = match potential {
+        BondPotential::Harmonic => parameters.stiffness / 2f32 * stretch * stretch,
+        BondPotential::Morse => {
+            let decay = (-parameters.morse_width * stretch).exp();
+            depth * (1f32 - decay) * (1f32 - decay)
+        }
+    };
+    well - depth
+}
@@ -1,18 +1,24 @@
-use bevy::{math::Vec2, prelude::Transform};
+use bevy_math::Vec2;
 
-const COLLISION_DAMPING: f32 = 0.5f32;
-use crate::{bounding_box, particle::Particle, particles_spawning};
-pub fn resolve_collisions(particle: &mut Particle, transform: &mut Transform) {
-    let half_bauds_size = bounding_box::BOX_BOUNDS_SIZE_PIXELS / 2f32
+use crate::{
+    config::SimulationConfig, particle::Particle, particles_spawning, units::WORLD_UNITS_PER_PM,
+};
+// returns the kinetic energy change from the damped bounce, u * pm^2 / ns^2
+pub fn resolve_collisions(particle: &mut Particle, config: &SimulationConfig) -> f32 {
+    let speed_squared = particle.velocity_pm_ns.length_squared();
+    // the drawn circle is sized in world units
+    let half_bauds_size = config.box_size_pm / 2f32
         - Vec2::ONE * particles_spawning::PARTICLE_RAY * particles_spawning::PARTICLE_RESOLUTION
-            / 2f32;
+            / 2f32
+            / WORLD_UNITS_PER_PM;
 
-    if transform.translation.x.abs() > half_bauds_size.x {
-        transform.translation.x = half_bauds_size.x * transform.translation.x.signum();
-        particle.velocity_pm_ns.x *= -1f32 * COLLISION_DAMPING;
+    if particle.position_pm.x.abs() > half_bauds_size.x {
+        particle.position_pm.x = half_bauds_size.x * particle.position_pm.x.signum();
+        particle.velocity_pm_ns.x *= -config.collision_damping;
     }
-    if transform.translation.y.abs() > half_bauds_size.y {
-        transform.translation.y = half_bauds_size.y * transform.translation.y.signum();
-        particle.velocity_pm_ns.y *= -1f32 * COLLISION_DAMPING;
+    if particle.position_pm.y.abs() > half_bauds_size.y {
+        particle.position_pm.y = half_bauds_size.y * particle.position_pm.y.signum();
+        particle.velocity_pm_ns.y *= -config.collision_damping;
     }
+    particle.element().mass_u / 2f32 * (particle.velocity_pm_ns.length_squared() - speed_squared)
 }
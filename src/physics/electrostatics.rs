@@ -0,0 +1,105 @@
+use bevy_math::Vec2;
+
+use crate::{element::Element, units::COULOMB_CONSTANT};
+
+// shifted force Coulomb, the force goes smoothly to zero at the cutoff so there is no jump when
+// a pair leaves the neighbourhood, and charges further than the cutoff are ignored
+pub fn shifted_coulomb_force(
+    position_pm: Vec2,
+    charge: f32,
+    other_position_pm: Vec2,
+    other_charge: f32,
+    min_distance_pm: f32,
+    cutoff_pm: f32,
+) -> Vec2 {
+    let offset = position_pm - other_position_pm;
+    let distance = offset.length();
+    if distance == 0f32 || distance >= cutoff_pm {
+        return Vec2::ZERO;
+    }
+    // overlapping atoms would get an almost infinite pull, so very close pairs act like at `min_distance_pm`
+    let force_distance = distance.max(min_distance_pm);
+    let strength = COULOMB_CONSTANT
+        * charge
+        * other_charge
+        * (1f32 / (force_distance * force_distance) - 1f32 / (cutoff_pm * cutoff_pm));
+
+    offset / distance * strength
+}
+
+// potential of `shifted_coulomb_force`, zero at the cutoff
+pub fn shifted_coulomb_energy(
+    distance_pm: f32,
+    charge: f32,
+    other_charge: f32,
+    min_distance_pm: f32,
+    cutoff_pm: f32,
+) -> f32 {
+    if distance_pm >= cutoff_pm {
+        return 0f32;
+    }
+    let strength = COULOMB_CONSTANT * charge * other_charge;
+    let energy_at = |distance: f32| {
+        strength
+            * (1f32 / distance - 1f32 / cutoff_pm
+                + (distance - cutoff_pm) / (cutoff_pm * cutoff_pm))
+    };
+    if distance_pm >= min_distance_pm {
+        return energy_at(distance_pm);
+    }
+    // the force stays the same below the min distance, so the energy grows linearly
+    let force =
+        strength * (1f32 / (min_distance_pm * min_distance_pm) - 1f32 / (cutoff_pm * cutoff_pm));
+    energy_at(min_distance_pm) + force * (min_distance_pm - distance_pm)
+}
+
+// ions can't get closer than their radii, covalent radii are used as there are no ionic ones in the table
+pub fn ionic_contact_distance(element: &Element, other_element: &Element) -> f32 {
+    (element.covalent_radius_pm + other_element.covalent_radius_pm) as f32
+}
+
+// Coulomb attraction plus Born repulsion `B / r^n`, with `B` picked so the force is zero at the
+// contact distance, keeps oppositely charged ions at the contact distance
+pub fn ionic_bond_force(
+    position_pm: Vec2,
+    charge: f32,
+    other_position_pm: Vec2,
+    other_charge: f32,
+    contact_distance_pm: f32,
+    repulsion_exponent: f32,
+) -> Vec2 {
+    let offset = position_pm - other_position_pm;
+    let distance = offset.length();
+    if distance == 0f32 {
+        return Vec2::ZERO;
+    }
+    // overlapping ions would get an almost infinite push, so cap it at half of the contact distance
+    let force_distance = distance.max(contact_distance_pm * 0.5f32);
+    let coulomb_strength =
+        COULOMB_CONSTANT * (charge * other_charge).abs() / (force_distance * force_distance);
+    let repulsion = (contact_distance_pm / force_distance).powf(repulsion_exponent - 1f32);
+
+    offset / distance * coulomb_strength * (repulsion - 1f32)
+}
+// potential of `ionic_bond_force`, lowest at the contact distance
+pub fn ionic_bond_energy(
+    distance_pm: f32,
+    charge: f32,
+    other_charge: f32,
+    contact_distance_pm: f32,
+    repulsion_exponent: f32,
+) -> f32 {
+    let strength = COULOMB_CONSTANT * (charge * other_charge).abs();
+    let energy_at = |distance: f32| {
+        -strength / distance
+            + strength * contact_distance_pm.powf(repulsion_exponent - 1f32)
+                / ((repulsion_exponent - 1f32) * distance.powf(repulsion_exponent - 1f32))
+    };
+    let min_distance_pm = contact_distance_pm * 0.5f32;
+    if distance_pm >= min_distance_pm {
+        return energy_at(distance_pm);
+    }
+    let force = strength / (min_distance_pm * min_distance_pm)
+        * ((contact_distance_pm / min_distance_pm).powf(repulsion_exponent - 1f32) - 1f32);
+    energy_at(min_distance_pm) + force * (min_distance_pm - distance_pm)
+}
@@ -0,0 +1,80 @@
+use bevy_math::Vec2;
+
+use crate::{element::Element, units::Energy};
+
+// Lorentz-Berthelot mixing, returns (epsilon in u * pm^2 / ns^2, sigma in pm)
+pub fn mixed_parameters(element: &Element, other_element: &Element) -> (f32, f32) {
+    let epsilon = Energy::from_kj_per_mol(
+        (element.lennard_jones_epsilon_kj_mol * other_element.lennard_jones_epsilon_kj_mol).sqrt(),
+    )
+    .u_pm2_ns2();
+    let sigma = (element.lennard_jones_sigma_pm + other_element.lennard_jones_sigma_pm) / 2f32;
+    (epsilon, sigma)
+}
+
+// shifted force 12-6 potential, repulsive closer than ~1.12 sigma and attractive up to the cutoff,
+// every non-bonded pair gets it so atoms can't pass through each other. The wall stops at
+// `max_energy`, pairs that can still bond pass `reactions::activation_energy` so a collision that
+// climbs that high gets through to the bond forming distance, the rest pass infinity
+pub fn lennard_jones_force(
+    position_pm: Vec2,
+    other_position_pm: Vec2,
+    epsilon: f32,
+    sigma_pm: f32,
+    cutoff_pm: f32,
+    max_energy: f32,
+) -> Vec2 {
+    let offset = position_pm - other_position_pm;
+    let distance = offset.length();
+    if distance == 0f32 || distance >= cutoff_pm {
+        return Vec2::ZERO;
+    }
+    // flat on top of the capped wall
+    if max_energy.is_finite()
+        && lennard_jones_energy(distance, epsilon, sigma_pm, cutoff_pm, f32::INFINITY) >= max_energy
+    {
+        return Vec2::ZERO;
+    }
+    // overlapping atoms (from spawning or the mouse) would get an almost infinite push
+    let force_distance = distance.max(sigma_pm * 0.7f32);
+    let strength = force_magnitude(force_distance, epsilon, sigma_pm)
+        - force_magnitude(cutoff_pm, epsilon, sigma_pm);
+
+    offset / distance * strength
+}
+// potential of `lennard_jones_force`, zero at the cutoff and never above `max_energy`
+pub fn lennard_jones_energy(
+    distance_pm: f32,
+    epsilon: f32,
+    sigma_pm: f32,
+    cutoff_pm: f32,
+    max_energy: f32,
+) -> f32 {
+    if distance_pm >= cutoff_pm {
+        return 0f32;
+    }
+    let cutoff_force = force_magnitude(cutoff_pm, epsilon, sigma_pm);
+    let energy_at = |distance: f32| {
+        let sigma_over_distance_6 = (sigma_pm / distance).powi(6);
+        let sigma_over_cutoff_6 = (sigma_pm / cutoff_pm).powi(6);
+        4f32 * epsilon
+            * (sigma_over_distance_6 * sigma_over_distance_6
+                - sigma_over_distance_6
+                - sigma_over_cutoff_6 * sigma_over_cutoff_6
+                + sigma_over_cutoff_6)
+            + (distance - cutoff_pm) * cutoff_force
+    };
+    let min_distance_pm = sigma_pm * 0.7f32;
+    let energy = if distance_pm >= min_distance_pm {
+        energy_at(distance_pm)
+    } else {
+        let force = force_magnitude(min_distance_pm, epsilon, sigma_pm) - cutoff_force;
+        energy_at(min_distance_pm) + force * (min_distance_pm - distance_pm)
+    };
+    energy.min(max_energy)
+}
+fn force_magnitude(distance_pm: f32, epsilon: f32, sigma_pm: f32) -> f32 {
+    let sigma_over_distance_6 = (sigma_pm / distance_pm).powi(6);
+    24f32 * epsilon / distance_pm
+        * (2f32 * sigma_over_distance_6 * sigma_over_distance_6 - sigma_over_distance_6)
+}
@@ -1,208 +1,554 @@
 use crate::{
+    angles, bond_potential, bond_table,
     collisions::resolve_collisions,
-    particle::{LookupParticle, Particle},
-    particle_grid::{self, get_connected_cells_indexes, pixel_pos_to_gird_pos},
-    particles_spawning::{self, PARTICLES_COUNT},
-    player_interaction_physics,
+    config::{Integrator, SimulationConfig},
+    electrostatics,
+    element::{Element, elements},
+    lennard_jones,
+    particle::{Bond, BondType, LookupParticle, Particle},
+    particle_grid::SpatialGrid,
+    reactions,
+    units::Energy,
 };
-use bevy::{math::*, prelude::*, window::PrimaryWindow};
+use bevy_math::Vec2;
+use bevy_tasks::{ComputeTaskPool, ParallelSlice, ParallelSliceMut, TaskPool};
 use core::f32;
+use std::collections::BTreeMap;
 
-// physics settings
-const TIME_SCALE_NS: f32 = 2f32; // nano seconds
-
-const RUN_PHYSICS: bool = true;
-const UPDATES_PER_FRAME: u32 = 3;
-pub fn handle_particles_physics(
-    mut particles: Query<(&mut Transform, &mut Particle)>,
-    time: Res<Time>,
-    q_window: Query<&Window, With<PrimaryWindow>>,
-    mouse_buttons: Res<ButtonInput<MouseButton>>,
-    q_camera: Query<(&Camera, &GlobalTransform)>,
+// runs one physics update for all particles
+pub fn step_particles(
+    particles: &mut [Particle],
+    grid: &mut SpatialGrid,
+    delta_ns: f32,
+    config: &SimulationConfig,
 ) {
-    if !RUN_PHYSICS {
-        return;
+    match config.integrator {
+        Integrator::Euler => {
+            grid.rebuild(particles, |particle| particle.position_pm, config);
+            reactions::apply_reactions(particles, grid, config);
+            calculate_forces(particles, grid, config);
+            for_each_particle_mut(particles, |particle| {
+                particle.velocity_pm_ns += particle.acceleration() * delta_ns;
+                move_particle(particle, delta_ns, config);
+            });
+            // the energy diagnostics reuse the grid
+            grid.rebuild(particles, |particle| particle.position_pm, config);
+        }
+        Integrator::VelocityVerlet => {
+            // forces from the end of the last step are the forces at the current positions
+            for_each_particle_mut(particles, |particle| {
+                particle.velocity_pm_ns += particle.acceleration() * delta_ns / 2f32;
+                move_particle(particle, delta_ns, config);
+            });
+            grid.rebuild(particles, |particle| particle.position_pm, config);
+            calculate_forces(particles, grid, config);
+            for_each_particle_mut(particles, |particle| {
+                particle.velocity_pm_ns += particle.acceleration() * delta_ns / 2f32;
+            });
+            // the next step starts from the forces of the new bonds
+            if reactions::apply_reactions(particles, grid, config) {
+                calculate_forces(particles, grid, config);
+            }
+        }
     }
-    let delta_ns = time.delta().as_secs_f32() * TIME_SCALE_NS / UPDATES_PER_FRAME as f32;
-    for _ in 0..UPDATES_PER_FRAME {
-        let mut particle_positions =
-            Vec::with_capacity(particles_spawning::PARTICLES_COUNT as usize);
+}
+fn move_particle(particle: &mut Particle, delta_ns: f32, config: &SimulationConfig) {
+    if particle.velocity_pm_ns.is_nan() {
+        particle.velocity_pm_ns = particle.last_velocity_pm_ns;
+    }
+    particle.last_velocity_pm_ns = particle.velocity_pm_ns;
 
-        for (_, particle) in &particles {
-            particle_positions.push(particle.position_pm.to_owned());
-        }
+    let s = particle.velocity_pm_ns * delta_ns;
 
-        // let connected_cells = particle_grid::calculate_connected_cells_for_every_particle(
-        //     &particle_predicted_positions,
-        // );
-        //
-        let grid = particle_grid::split_particles_into_grid(&particle_positions);
-
-        // let densities = &pressure_handler::calculate_density_for_every_particle(
-        //     &grid,
-        //     &particle_predicted_positions,
-        //     &connected_cells,
-        // );
-
-        player_interaction_physics::calculate_player_interaction_effect(
-            &mut particles,
-            &q_window,
-            &mouse_buttons,
-            &q_camera,
-            delta_ns,
-        );
-        let lookup = create_particle_lookup(&particles);
-        handle_chemical_bonds(delta_ns, &mut particles, &grid, lookup);
-
-        particles
-            .par_iter_mut()
-            .for_each(|(mut transform, mut particle)| {
-                if particle.velocity_pm_ns.is_nan() {
-                    particle.velocity_pm_ns = particle.last_velocity_pm_ns;
-                }
-                particle.last_velocity_pm_ns = particle.velocity_pm_ns;
+    particle.position_pm += s;
 
-                let s = particle.velocity_pm_ns * delta_ns;
+    particle.wall_work = resolve_collisions(particle, config);
+}
+// sums up every force acting on every particle into `Particle::force`, `grid` has to be rebuilt at
+// the current positions. Reactions run outside of it, only where positions and velocities belong
+// to the same moment, so the energy they exchange is the energy the diagnostics measure
+fn calculate_forces(particles: &mut [Particle], grid: &SpatialGrid, config: &SimulationConfig) {
+    // let densities = &pressure_handler::calculate_density_for_every_particle(
+    //     &grid,
+    //     &particle_predicted_positions,
+    //     &connected_cells,
+    // );
 
-                particle.position_pm += s;
-                transform.translation = vec3(particle.position_pm.x, particle.position_pm.y, 0f32);
+    for_each_particle_mut(particles, |particle| {
+        particle.force = Vec2::ZERO;
+        particle.update_partial_charge(config);
+    });
 
-                resolve_collisions(&mut particle, &mut transform);
-            });
-    }
+    let lookup = create_particle_lookup(particles);
+    apply_bond_forces(particles, &lookup, config);
+    apply_angle_forces(particles, &lookup, config);
+    apply_non_bonded_forces(particles, grid, &lookup, config);
+}
+// longest step where no particle moves further than `max_displacement_pm`, counting both its
+// velocity and the push from the current force
+pub fn stable_timestep(particles: &[Particle], max_displacement_pm: f32) -> f32 {
+    particles
+        .iter()
+        .map(|particle| {
+            let speed = particle.velocity_pm_ns.length();
+            let acceleration = particle.acceleration().length();
+            let from_speed = max_displacement_pm / speed;
+            let from_force = (2f32 * max_displacement_pm / acceleration).sqrt();
+            from_speed.min(from_force)
+        })
+        .filter(|timestep| !timestep.is_nan())
+        .fold(f32::INFINITY, f32::min)
+}
+// works outside of a bevy app too, the compute task pool gets created when it doesn't exist yet
+pub fn for_each_particle_mut(
+    mut particles: &mut [Particle],
+    f: impl Fn(&mut Particle) + Send + Sync,
+) {
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+    particles.par_splat_map_mut(task_pool, None, |_, chunk| {
+        chunk.iter_mut().for_each(&f);
+    });
 }
-fn create_particle_lookup(
-    particles: &Query<(&mut Transform, &mut Particle)>,
-) -> Vec<LookupParticle> {
-    let mut output = Vec::with_capacity(PARTICLES_COUNT as usize);
-    particles.iter().for_each(|(_, particle)| {
+fn create_particle_lookup(particles: &[Particle]) -> Vec<LookupParticle> {
+    let mut output = Vec::with_capacity(particles.len());
+    particles.iter().for_each(|particle| {
         output.push(LookupParticle {
             element_index: particle.element_index,
-            current_unused_valence_electrons: particle.connected_electrons_needed.to_owned(),
             bonds_particle_index: particle.bonds.clone(),
             position_pm: particle.position_pm,
             connected_electrons_needed: particle.connected_electrons_needed,
+            formal_charge: particle.formal_charge,
+            partial_charge: particle.partial_charge,
         });
     });
     output
 }
-pub const BOND_DISTANCE: f32 = 30f32;
-
-fn handle_chemical_bonds(
-    delta_ns: f32,
-    particles: &mut Query<(&mut Transform, &mut Particle)>,
-    particles_grid: &[Vec<usize>],
-    lookup: Vec<LookupParticle>,
+// bonds are only created and broken in `reactions`, here they just pull
+fn apply_bond_forces(
+    particles: &mut [Particle],
+    lookup: &[LookupParticle],
+    config: &SimulationConfig,
 ) {
-    particles.par_iter_mut().for_each(|(_, mut mut_particle)| {
-        mut_particle.break_all_out_of_range_bonds();
+    for_each_particle_mut(particles, |mut_particle| {
+        let bonded_indexes: Vec<usize> = mut_particle.bonds.keys().copied().collect();
+        for target_index in bonded_indexes {
+            let target_particle = &lookup[target_index];
+            let Some(mut_bond) = mut_particle.bonds.get_mut(&target_index) else {
+                continue;
+            };
+            let bond_type = mut_bond.bond_type;
+            let electrons_used = mut_bond.electrons_used;
+            mut_bond.bonded_pos = target_particle.position_pm;
 
-        mut_particle.particles_in_range.clear();
-        for cell in get_connected_cells_indexes(&pixel_pos_to_gird_pos(&mut_particle.position_pm)) {
-            for target_index in particles_grid[cell].to_owned() {
-                let target_particle = &lookup[target_index];
-
-                if mut_particle.index == target_index {
-                    continue;
-                }
-
-                let target_bond_option = target_particle
-                    .bonds_particle_index
-                    .get(&mut_particle.index);
-                let mut contains_bond_with_target = false;
-                let mut should_break_bond_with_target = false;
-
-                if let Some(mut_bond) = mut_particle.bonds.get_mut(&target_index) {
-                    should_break_bond_with_target = mut_bond.should_break;
-                    mut_bond.bonded_pos = target_particle.position_pm;
-                    contains_bond_with_target = true;
-
-                    keep_bond_distance(delta_ns, &mut mut_particle, target_particle);
-                }
+            keep_bond_distance(
+                mut_particle,
+                target_particle,
+                bond_type,
+                electrons_used,
+                config,
+            );
+        }
+    });
+}
+// angle terms where the particle is the central atom and where it is at the end of one of the
+// bonds, bonds are taken from the lookup so both sides see the same molecule
+fn apply_angle_forces(
+    particles: &mut [Particle],
+    lookup: &[LookupParticle],
+    config: &SimulationConfig,
+) {
+    for_each_particle_mut(particles, |mut_particle| {
+        let looked_up = &lookup[mut_particle.index];
+        let bonded: Vec<usize> = angle_bonded_indexes(looked_up).collect();
+        let mut force = Vec2::ZERO;
 
-                if contains_bond_with_target {
-                    if let Some(target_bond) = target_bond_option {
-                        // when mut and target does have bond
-                        if target_bond.should_break {
-                            mut_particle.break_bond_find_bond(&target_index);
-                        }
-                    } else if should_break_bond_with_target {
-                        mut_particle.break_bond_find_bond(&target_index);
-                    }
-                } else if let Some(target_bond) = target_bond_option {
-                    // when mut doesn't but target does have bond
-                    if !target_bond.should_break {
-                        if target_bond.electrons_used > mut_particle.connected_electrons_needed {
-                            // when bond is bigger than you have free electrons
-                            // register fake bond that shows that target has to brake the bond that
-                            // is too big
-                            mut_particle.register_bond(target_index, 0, true, target_particle);
-                        } else {
-                            mut_particle.register_bond(
-                                target_index,
-                                target_bond.electrons_used,
-                                false,
-                                target_particle,
-                            );
-                        }
-                    }
-                } else {
-                    // when mut and target doesn't have bond
-                    try_creating_bond(&mut mut_particle, target_particle, target_index);
+        if bonded.len() >= 2 {
+            let equilibrium_angle = angles::equilibrium_angle(
+                mut_particle.element(),
+                &looked_up.bonds_particle_index,
+                looked_up.formal_charge,
+            );
+            for (i, &end_index) in bonded.iter().enumerate() {
+                for &other_end_index in &bonded[i + 1..] {
+                    force += angles::angle_force_on_center(
+                        mut_particle.position_pm,
+                        lookup[end_index].position_pm,
+                        lookup[other_end_index].position_pm,
+                        equilibrium_angle,
+                        config.angle_stiffness,
+                    );
                 }
+            }
+        }
 
-                mut_particle
-                    .particles_in_range
-                    .push(target_particle.position_pm);
+        for &center_index in &bonded {
+            let center = &lookup[center_index];
+            let equilibrium_angle = angles::equilibrium_angle(
+                &elements()[center.element_index as usize],
+                &center.bonds_particle_index,
+                center.formal_charge,
+            );
+            for other_end_index in
+                angle_bonded_indexes(center).filter(|&index| index != mut_particle.index)
+            {
+                force += angles::angle_force_on_end(
+                    center.position_pm,
+                    mut_particle.position_pm,
+                    lookup[other_end_index].position_pm,
+                    equilibrium_angle,
+                    config.angle_stiffness,
+                );
             }
         }
+        mut_particle.force += force;
     });
 }
-fn try_creating_bond(
-    mut_particle: &mut Particle,
-    target_particle: &LookupParticle,
+fn angle_bonded_indexes(particle: &LookupParticle) -> impl Iterator<Item = usize> + '_ {
+    particle
+        .bonds_particle_index
+        .iter()
+        .filter(|(_, bond)| angles::is_angle_bond(bond))
+        .map(|(&index, _)| index)
+}
+// Coulomb and van der Waals forces from non-bonded particles in neighbouring cells, bonded
+// particles and particles bonded to the same atom are skipped as the bonds already keep them in place
+fn apply_non_bonded_forces(
+    particles: &mut [Particle],
+    grid: &SpatialGrid,
+    lookup: &[LookupParticle],
+    config: &SimulationConfig,
+) {
+    for_each_particle_mut(particles, |mut_particle| {
+        let charge = mut_particle.charge();
+        let mut force = Vec2::ZERO;
+        mut_particle.particles_in_range.clear();
+        for target_index in grid.neighbours(mut_particle.position_pm) {
+            let target_particle = &lookup[target_index];
+            let target_charge =
+                target_particle.formal_charge as f32 + target_particle.partial_charge;
+            if mut_particle.index == target_index {
+                continue;
+            }
+            mut_particle
+                .particles_in_range
+                .push(target_particle.position_pm);
+            if is_excluded(
+                &mut_particle.bonds,
+                target_index,
+                &target_particle.bonds_particle_index,
+            ) {
+                continue;
+            }
+            let target_element = &elements()[target_particle.element_index as usize];
+            let (epsilon, sigma_pm) =
+                lennard_jones::mixed_parameters(mut_particle.element(), target_element);
+            force += lennard_jones::lennard_jones_force(
+                mut_particle.position_pm,
+                target_particle.position_pm,
+                epsilon,
+                sigma_pm,
+                config.smoothing_distance.pm(),
+                wall_height(
+                    mut_particle.element(),
+                    mut_particle.connected_electrons_needed,
+                    target_element,
+                    target_particle.connected_electrons_needed,
+                    config,
+                ),
+            );
+            if charge == 0f32 || target_charge == 0f32 {
+                continue;
+            }
+            force += electrostatics::shifted_coulomb_force(
+                mut_particle.position_pm,
+                charge,
+                target_particle.position_pm,
+                target_charge,
+                (mut_particle.element().covalent_radius_pm + target_element.covalent_radius_pm)
+                    as f32,
+                config.smoothing_distance.pm(),
+            );
+        }
+        mut_particle.force += force;
+    });
+}
+// bonded pairs and pairs bonded to the same atom
+pub fn is_excluded(
+    bonds: &BTreeMap<usize, Bond>,
     target_index: usize,
+    target_bonds: &BTreeMap<usize, Bond>,
+) -> bool {
+    bonds.contains_key(&target_index)
+        || bonds
+            .keys()
+            .any(|bonded_index| target_bonds.contains_key(bonded_index))
+}
+#[derive(Clone, Copy, Default)]
+pub struct PotentialEnergy {
+    pub bonds: Energy,
+    pub angles: Energy,
+    pub non_bonded: Energy,
+}
+// potentials of every force from `calculate_forces`, every pair and angle is counted once,
+// `grid` has to be rebuilt at the current positions like the one `step_particles` leaves behind
+pub fn potential_energy(
+    particles: &[Particle],
+    grid: &SpatialGrid,
+    config: &SimulationConfig,
+) -> PotentialEnergy {
+    let task_pool = ComputeTaskPool::get_or_init(TaskPool::default);
+
+    particles
+        .par_splat_map(task_pool, None, |_, chunk| {
+            let mut energy = PotentialEnergy::default();
+            for particle in chunk {
+                add_particle_potential_energy(particle, particles, grid, config, &mut energy);
+            }
+            energy
+        })
+        .into_iter()
+        .fold(PotentialEnergy::default(), |sum, energy| PotentialEnergy {
+            bonds: sum.bonds + energy.bonds,
+            angles: sum.angles + energy.angles,
+            non_bonded: sum.non_bonded + energy.non_bonded,
+        })
+}
+fn add_particle_potential_energy(
+    particle: &Particle,
+    particles: &[Particle],
+    grid: &SpatialGrid,
+    config: &SimulationConfig,
+    energy: &mut PotentialEnergy,
 ) {
-    if mut_particle
-        .position_pm
-        // use distance squared for speed
-        .distance(target_particle.position_pm)
-        > BOND_DISTANCE
-    {
-        return;
+    for (&target_index, bond) in particle.bonds.range(particle.index + 1..) {
+        energy.bonds += bond_energy(particle, &particles[target_index], bond, config);
     }
-    // this is very naive and needs to be changed
 
-    let connected_electrons = mut_particle
-        .connected_electrons_needed
-        .min(target_particle.connected_electrons_needed);
-    if connected_electrons == 0 {
-        return;
+    energy.angles += center_angles_energy(particle, particles, config, |_, _| true);
+
+    for target_index in grid.neighbours(particle.position_pm) {
+        let target_particle = &particles[target_index];
+        if target_index <= particle.index
+            || is_excluded(&particle.bonds, target_index, &target_particle.bonds)
+        {
+            continue;
+        }
+        energy.non_bonded += non_bonded_energy(particle, target_particle, config);
+    }
+}
+// angle terms with `center` as the central atom, `counted` gets both end indexes and picks the
+// terms that are summed
+fn center_angles_energy(
+    center: &Particle,
+    particles: &[Particle],
+    config: &SimulationConfig,
+    counted: impl Fn(usize, usize) -> bool,
+) -> Energy {
+    let bonded: Vec<usize> = center
+        .bonds
+        .iter()
+        .filter(|(_, bond)| angles::is_angle_bond(bond))
+        .map(|(&index, _)| index)
+        .collect();
+    if bonded.len() < 2 {
+        return Energy::default();
+    }
+    let equilibrium_angle =
+        angles::equilibrium_angle(center.element(), &center.bonds, center.formal_charge);
+    let mut energy = 0f32;
+    for (i, &end_index) in bonded.iter().enumerate() {
+        for &other_end_index in &bonded[i + 1..] {
+            if !counted(end_index, other_end_index) {
+                continue;
+            }
+            energy += angles::angle_energy(
+                center.position_pm,
+                particles[end_index].position_pm,
+                particles[other_end_index].position_pm,
+                equilibrium_angle,
+                config.angle_stiffness,
+            );
+        }
+    }
+    Energy::from_u_pm2_ns2(energy)
+}
+// every term of `potential_energy` that involves at least one of `indexes`,
+// changing the bonds of these particles changes only these terms
+pub fn local_potential_energy(
+    particles: &[Particle],
+    grid: &SpatialGrid,
+    indexes: &[usize],
+    config: &SimulationConfig,
+) -> Energy {
+    let mut energy = Energy::default();
+    // terms between two of `indexes` are counted from the smaller index only
+    let counted_from =
+        |index: usize, other_index: usize| !indexes.contains(&other_index) || index < other_index;
+
+    let mut angle_centers: Vec<usize> = indexes.to_vec();
+    for &index in indexes {
+        let particle = &particles[index];
+        for (&target_index, bond) in &particle.bonds {
+            if counted_from(index, target_index) {
+                energy += bond_energy(particle, &particles[target_index], bond, config);
+            }
+            if !angle_centers.contains(&target_index) {
+                angle_centers.push(target_index);
+            }
+        }
+
+        for target_index in grid.neighbours(particle.position_pm) {
+            let target_particle = &particles[target_index];
+            if target_index == index
+                || !counted_from(index, target_index)
+                || is_excluded(&particle.bonds, target_index, &target_particle.bonds)
+            {
+                continue;
+            }
+            energy += non_bonded_energy(particle, target_particle, config);
+        }
     }
 
-    mut_particle.register_bond(target_index, connected_electrons, false, target_particle);
+    for center_index in angle_centers {
+        let center_in_indexes = indexes.contains(&center_index);
+        energy += center_angles_energy(
+            &particles[center_index],
+            particles,
+            config,
+            |end_index, other_end_index| {
+                center_in_indexes
+                    || indexes.contains(&end_index)
+                    || indexes.contains(&other_end_index)
+            },
+        );
+    }
+    energy
+}
+// potential of the bond force between the two particles, covalent bonds start at minus their
+// dissociation energy so forming them releases it
+pub fn bond_energy(
+    particle: &Particle,
+    target_particle: &Particle,
+    bond: &Bond,
+    config: &SimulationConfig,
+) -> Energy {
+    let element = particle.element();
+    let target_element = target_particle.element();
+    let distance = particle.position_pm.distance(target_particle.position_pm);
+    Energy::from_u_pm2_ns2(match bond.bond_type {
+        BondType::Covalent => bond_potential::bond_energy(
+            &bond_table::bond_parameters(element, target_element, bond.electrons_used, config),
+            distance,
+            config.bond_potential,
+        ),
+        BondType::Ionic => electrostatics::ionic_bond_energy(
+            distance,
+            particle.formal_charge as f32,
+            target_particle.formal_charge as f32,
+            electrostatics::ionic_contact_distance(element, target_element),
+            config.ionic_repulsion_exponent,
+        ),
+    })
+}
+// potential of the van der Waals and Coulomb forces between two particles that aren't bonded
+pub fn non_bonded_energy(
+    particle: &Particle,
+    target_particle: &Particle,
+    config: &SimulationConfig,
+) -> Energy {
+    let element = particle.element();
+    let target_element = target_particle.element();
+    let distance = particle.position_pm.distance(target_particle.position_pm);
+    let (epsilon, sigma_pm) = lennard_jones::mixed_parameters(element, target_element);
+    Energy::from_u_pm2_ns2(
+        lennard_jones::lennard_jones_energy(
+            distance,
+            epsilon,
+            sigma_pm,
+            config.smoothing_distance.pm(),
+            wall_height(
+                element,
+                particle.connected_electrons_needed,
+                target_element,
+                target_particle.connected_electrons_needed,
+                config,
+            ),
+        ) + electrostatics::shifted_coulomb_energy(
+            distance,
+            particle.charge(),
+            target_particle.charge(),
+            (element.covalent_radius_pm + target_element.covalent_radius_pm) as f32,
+            config.smoothing_distance.pm(),
+        ),
+    )
+}
+// u * pm^2 / ns^2, the van der Waals wall of pairs that can still bond ends at their activation
+// energy, the wall of every other pair goes on
+fn wall_height(
+    element: &Element,
+    connected_electrons_needed: u8,
+    target_element: &Element,
+    target_connected_electrons_needed: u8,
+    config: &SimulationConfig,
+) -> f32 {
+    reactions::activation_energy(
+        element,
+        connected_electrons_needed,
+        target_element,
+        target_connected_electrons_needed,
+        config,
+    )
+    .map_or(f32::INFINITY, Energy::u_pm2_ns2)
 }
-const BOND_DISTANCE_KEEPING_STRENGTH: f32 = 10f32;
 fn keep_bond_distance(
-    delta_ns: f32,
     mut_particle: &mut Particle,
     target_particle: &LookupParticle,
+    bond_type: BondType,
+    electrons_used: u8,
+    config: &SimulationConfig,
 ) {
-    if target_particle.position_pm.x == f32::NAN || mut_particle.position_pm.x == f32::NAN {
+    if target_particle.position_pm.is_nan() || mut_particle.position_pm.is_nan() {
+        return;
+    }
+    if bond_type == BondType::Ionic {
+        keep_ionic_bond(mut_particle, target_particle, config);
         return;
     }
 
-    let needed_distance_change = mut_particle
-        .position_pm
-        .distance(target_particle.position_pm)
-        - BOND_DISTANCE;
+    // pulls towards the equilibrium length of this pair and bond order
+    let bond_parameters = bond_table::bond_parameters(
+        mut_particle.element(),
+        &elements()[target_particle.element_index as usize],
+        electrons_used,
+        config,
+    );
+    let force_strength = bond_potential::bond_force(
+        &bond_parameters,
+        mut_particle
+            .position_pm
+            .distance(target_particle.position_pm),
+        config.bond_potential,
+    );
+    let force_direction =
+        (target_particle.position_pm - mut_particle.position_pm).normalize_or_zero();
 
-    let force_strength = BOND_DISTANCE_KEEPING_STRENGTH * needed_distance_change;
-    let force_direction = (target_particle.position_pm - mut_particle.position_pm).normalize();
+    mut_particle.force += force_strength * force_direction;
+}
+// ions are held together by their charges instead of a fixed distance spring
+fn keep_ionic_bond(
+    mut_particle: &mut Particle,
+    target_particle: &LookupParticle,
+    config: &SimulationConfig,
+) {
+    let target_element = &elements()[target_particle.element_index as usize];
+    let force = electrostatics::ionic_bond_force(
+        mut_particle.position_pm,
+        mut_particle.formal_charge as f32,
+        target_particle.position_pm,
+        target_particle.formal_charge as f32,
+        electrostatics::ionic_contact_distance(mut_particle.element(), target_element),
+        config.ionic_repulsion_exponent,
+    );
 
-    mut_particle.position_pm += force_strength * force_direction  * delta_ns/* / mut_particle.element().mass_u as f32 */;
-    // println!(
-    //     "bond str: {}, {}, {} - {}",
-    //     force_strength, force_direction, target_particle.position_pm, mut_particle.position_pm
-    // );
+    mut_particle.force += force;
 }
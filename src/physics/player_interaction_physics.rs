@@ -1,9 +1,16 @@
-use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_math::Vec2;
 
-use crate::particle::Particle;
+use crate::{particle::Particle, particle_physics::for_each_particle_mut};
 
-const INTERACTION_STRENGTH: f32 = 900000f32;
+// scaled with the square of the time scale so the pull looks the same on screen
+const INTERACTION_STRENGTH: f32 = 3.6e14f32;
 const MAX_INTERACTION_DIST_SQRT: f32 = 90000f32;
+
+#[derive(Clone, Copy)]
+pub struct PlayerInteraction {
+    pub mouse_position: Vec2,
+    pub force_sign: f32,
+}
 pub fn calculate_interaction_force(
     pos: Vec2,
     mouse_pos: Vec2,
@@ -12,7 +19,7 @@ pub fn calculate_interaction_force(
 ) -> Vec2 {
     let dist = mouse_pos.distance_squared(pos);
     if dist > MAX_INTERACTION_DIST_SQRT || dist == 0f32 {
-        return Vec2::ONE;
+        return Vec2::ZERO;
     }
 
     let dir = (mouse_pos - pos) / dist;
@@ -22,61 +29,19 @@ pub fn calculate_interaction_force(
     strength * dir
 }
 
-pub fn calculate_player_interaction_effect(
-    particles: &mut Query<(&mut Transform, &mut Particle)>,
-    q_window: &Query<'_, '_, &Window, With<PrimaryWindow>>,
-    mouse_buttons: &Res<'_, ButtonInput<MouseButton>>,
-    q_camera: &Query<'_, '_, (&Camera, &GlobalTransform)>,
+pub fn apply_player_interaction(
+    particles: &mut [Particle],
+    interaction: PlayerInteraction,
     delta: f32,
 ) {
-    // interactions
-    let mut use_interaction: bool = true;
-    // get the camera info and transform
-    // assuming there is exactly one main camera entity, so Query::single() is OK
-    let (camera, camera_transform) = q_camera.single();
-
-    // There is only one primary window, so we can similarly get it from the query:
-    let window = q_window.single();
-
-    // check if the cursor is inside the window and get its position
-    // then, ask bevy to convert into world coordinates, and truncate to discard Z
-    let cursor_option = window.cursor_position();
-    let mouse_position = match cursor_option {
-        Some(pos) => camera
-            .viewport_to_world(camera_transform, pos)
-            .map(|ray| ray.origin.truncate())
-            .unwrap(),
-        None => {
-            use_interaction = false;
-            Vec2::ZERO
-        }
-    };
-
-    let force_sign;
-    if mouse_buttons.pressed(MouseButton::Right) {
-        force_sign = -1f32;
-    } else if mouse_buttons.pressed(MouseButton::Left) {
-        // disabled because not working good enough
-        use_interaction = false;
-        force_sign = 0.5f32;
-    } else {
-        use_interaction = false;
-        force_sign = 0f32;
-    };
-
-    particles.par_iter_mut().for_each(|(_, mut particle)| {
-        let interaction_force = match use_interaction {
-            true => calculate_interaction_force(
-                particle.position_pm,
-                mouse_position,
-                force_sign,
-                particle.velocity_pm_ns,
-            ),
-            false => Vec2::ZERO,
-        };
-
-        let force = interaction_force;
-        let acceleration = force / particle.element().mass_u as f32;
+    for_each_particle_mut(particles, |particle| {
+        let force = calculate_interaction_force(
+            particle.position_pm,
+            interaction.mouse_position,
+            interaction.force_sign,
+            particle.velocity_pm_ns,
+        );
+        let acceleration = force / particle.element().mass_u;
 
         particle.velocity_pm_ns += acceleration * delta;
     });
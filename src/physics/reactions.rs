@@ -0,0 +1,420 @@
+use bevy_math::Vec2;
+
+use crate::{
+    bond_table,
+    config::SimulationConfig,
+    element::Element,
+    lennard_jones,
+    particle::{BondType, Particle, bond_forming_distance},
+    particle_grid::SpatialGrid,
+    particle_physics::{bond_energy, local_potential_energy},
+    units::{Energy, Mass},
+};
+
+// bonds form and break one pair at a time, so both atoms always agree about their bonds. The
+// potential energy gained or lost by every term around the two atoms is taken from or given to
+// their motion along the line between them, reactions keep both the energy and the momentum.
+// True when a bond formed or broke, the forces have to be calculated again then
+pub fn apply_reactions(
+    particles: &mut [Particle],
+    grid: &SpatialGrid,
+    config: &SimulationConfig,
+) -> bool {
+    let dissociated = dissociate_bonds(particles, grid, config);
+    let formed = form_bonds(particles, grid, config);
+    dissociated || formed
+}
+
+// bonds holding at least their dissociation energy break while the atoms fly apart, the bond
+// energy is zero for atoms pulled infinitely far apart, so a bond breaks once its potential plus
+// the kinetic energy of the atoms along the bond reach zero
+fn dissociate_bonds(
+    particles: &mut [Particle],
+    grid: &SpatialGrid,
+    config: &SimulationConfig,
+) -> bool {
+    let mut dissociated = false;
+    for index in 0..particles.len() {
+        let bonded_indexes: Vec<usize> = particles[index]
+            .bonds
+            .range(index + 1..)
+            .map(|(&target_index, _)| target_index)
+            .collect();
+
+        for target_index in bonded_indexes {
+            let speed = approach_speed(particles, index, target_index);
+            if speed >= 0f32 {
+                continue;
+            }
+            let particle = &particles[index];
+            let target_particle = &particles[target_index];
+            let bond = particle.bonds[&target_index].clone();
+            let stored_energy = bond_energy(particle, target_particle, &bond, config)
+                + radial_kinetic_energy(particle, target_particle, speed);
+            if stored_energy < Energy::default() {
+                continue;
+            }
+
+            let energy_before =
+                local_potential_energy(particles, grid, &[index, target_index], config);
+            unbond(particles, index, target_index, config);
+            let energy_after =
+                local_potential_energy(particles, grid, &[index, target_index], config);
+
+            // the rest of the molecule can still hold the atoms back
+            if exchange_energy(particles, index, target_index, energy_after - energy_before) {
+                dissociated = true;
+            } else {
+                bond_pair(
+                    particles,
+                    index,
+                    target_index,
+                    bond.electrons_used,
+                    bond.bond_type,
+                    config,
+                );
+            }
+        }
+    }
+    dissociated
+}
+
+// pairs with free electrons that collide harder than the activation energy bond, pairs are
+// tried in index order so runs stay reproducible
+fn form_bonds(particles: &mut [Particle], grid: &SpatialGrid, config: &SimulationConfig) -> bool {
+    let mut formed = false;
+    'particles: for index in 0..particles.len() {
+        for target_index in grid.neighbours(particles[index].position_pm) {
+            if particles[index].connected_electrons_needed == 0 {
+                continue 'particles;
+            }
+            if target_index > index {
+                formed |= try_forming_bond(particles, grid, index, target_index, config);
+            }
+        }
+    }
+    formed
+}
+fn try_forming_bond(
+    particles: &mut [Particle],
+    grid: &SpatialGrid,
+    index: usize,
+    target_index: usize,
+    config: &SimulationConfig,
+) -> bool {
+    let particle = &particles[index];
+    let target_particle = &particles[target_index];
+    if particle.bonds.contains_key(&target_index) {
+        return false;
+    }
+    let Some(activation_energy) = activation_energy(
+        particle.element(),
+        particle.connected_electrons_needed,
+        target_particle.element(),
+        target_particle.connected_electrons_needed,
+        config,
+    ) else {
+        return false;
+    };
+    // this is very naive and needs to be changed
+    let connected_electrons = particle
+        .connected_electrons_needed
+        .min(target_particle.connected_electrons_needed);
+    let bond_type = BondType::between(
+        particle.element(),
+        target_particle.element(),
+        config.ionic_electronegativity_difference,
+    );
+    let distance = particle.position_pm.distance(target_particle.position_pm);
+    if distance
+        > bond_forming_distance(
+            particle.element(),
+            target_particle.element(),
+            bond_type,
+            connected_electrons,
+            config,
+        )
+    {
+        return false;
+    }
+
+    // the van der Waals wall is the barrier, whatever part of it the pair didn't climb yet still
+    // has to be paid by the collision
+    let (epsilon, sigma_pm) =
+        lennard_jones::mixed_parameters(particle.element(), target_particle.element());
+    let climbed_energy = Energy::from_u_pm2_ns2(
+        lennard_jones::lennard_jones_energy(
+            distance,
+            epsilon,
+            sigma_pm,
+            config.smoothing_distance.pm(),
+            activation_energy.u_pm2_ns2(),
+        )
+        .max(0f32),
+    );
+    let speed = approach_speed(particles, index, target_index);
+    if speed <= 0f32
+        || radial_kinetic_energy(particle, target_particle, speed) + climbed_energy
+            < activation_energy
+    {
+        return false;
+    }
+
+    let energy_before = local_potential_energy(particles, grid, &[index, target_index], config);
+    bond_pair(
+        particles,
+        index,
+        target_index,
+        connected_electrons,
+        bond_type,
+        config,
+    );
+    let energy_after = local_potential_energy(particles, grid, &[index, target_index], config);
+
+    if !exchange_energy(particles, index, target_index, energy_after - energy_before) {
+        unbond(particles, index, target_index, config);
+        return false;
+    }
+    true
+}
+
+// collision energy two atoms need to bond, none when one of them has no electrons left to share
+pub fn activation_energy(
+    element: &Element,
+    connected_electrons_needed: u8,
+    other_element: &Element,
+    other_connected_electrons_needed: u8,
+    config: &SimulationConfig,
+) -> Option<Energy> {
+    let connected_electrons = connected_electrons_needed.min(other_connected_electrons_needed);
+    if connected_electrons == 0 {
+        return None;
+    }
+    Some(
+        match BondType::between(
+            element,
+            other_element,
+            config.ionic_electronegativity_difference,
+        ) {
+            BondType::Covalent => {
+                bond_table::bond_parameters(element, other_element, connected_electrons, config)
+                    .activation_energy
+            }
+            BondType::Ionic => config.reaction_activation_energy_kj_mol,
+        },
+    )
+}
+fn bond_pair(
+    particles: &mut [Particle],
+    index: usize,
+    target_index: usize,
+    electrons_used: u8,
+    bond_type: BondType,
+    config: &SimulationConfig,
+) {
+    let (element_index, position_pm) =
+        (particles[index].element_index, particles[index].position_pm);
+    let (target_element_index, target_position_pm) = (
+        particles[target_index].element_index,
+        particles[target_index].position_pm,
+    );
+    particles[index].register_bond(
+        target_index,
+        electrons_used,
+        bond_type,
+        target_element_index,
+        target_position_pm,
+    );
+    particles[target_index].register_bond(
+        index,
+        electrons_used,
+        bond_type,
+        element_index,
+        position_pm,
+    );
+    particles[index].update_partial_charge(config);
+    particles[target_index].update_partial_charge(config);
+}
+fn unbond(
+    particles: &mut [Particle],
+    index: usize,
+    target_index: usize,
+    config: &SimulationConfig,
+) {
+    particles[index].break_bond(&target_index);
+    particles[target_index].break_bond(&index);
+    particles[index].update_partial_charge(config);
+    particles[target_index].update_partial_charge(config);
+}
+
+fn reduced_mass(particle: &Particle, target_particle: &Particle) -> Mass {
+    let mass = particle.element().mass_u;
+    let target_mass = target_particle.element().mass_u;
+    Mass::from_u(mass * target_mass / (mass + target_mass))
+}
+// energy of the motion of the pair along the line between them
+fn radial_kinetic_energy(particle: &Particle, target_particle: &Particle, speed: f32) -> Energy {
+    Energy::kinetic(
+        reduced_mass(particle, target_particle),
+        Vec2::new(speed, 0f32),
+    )
+}
+// pm / ns, positive when the particles get closer
+fn approach_speed(particles: &[Particle], index: usize, target_index: usize) -> f32 {
+    let particle = &particles[index];
+    let target_particle = &particles[target_index];
+    let direction = (target_particle.position_pm - particle.position_pm).normalize_or_zero();
+    (particle.velocity_pm_ns - target_particle.velocity_pm_ns).dot(direction)
+}
+// takes `energy_change` from the motion of the pair along the line between them, or gives it
+// when negative, false and nothing changes when they don't move fast enough to pay for it
+fn exchange_energy(
+    particles: &mut [Particle],
+    index: usize,
+    target_index: usize,
+    energy_change: Energy,
+) -> bool {
+    let particle = &particles[index];
+    let target_particle = &particles[target_index];
+    let direction = (target_particle.position_pm - particle.position_pm).normalize_or_zero();
+    if direction == Vec2::ZERO || !energy_change.u_pm2_ns2().is_finite() {
+        return false;
+    }
+    let reduced_mass = reduced_mass(particle, target_particle).u();
+    let speed = approach_speed(particles, index, target_index);
+    let kinetic_energy = radial_kinetic_energy(particle, target_particle, speed);
+    if kinetic_energy < energy_change {
+        return false;
+    }
+
+    // the pair keeps moving the same way, towards or away from each other
+    let new_speed = (2f32 * (kinetic_energy - energy_change).u_pm2_ns2() / reduced_mass)
+        .sqrt()
+        .copysign(speed);
+    let impulse = direction * reduced_mass * (new_speed - speed);
+    let mass = particles[index].element().mass_u;
+    let target_mass = particles[target_index].element().mass_u;
+    particles[index].velocity_pm_ns += impulse / mass;
+    particles[target_index].velocity_pm_ns -= impulse / target_mass;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        diagnostics::kinetic_energy,
+        element::{elements, find_element_index},
+    };
+
+    // an H and an O `distance_pm` apart along x, both also moving sideways so only a part of their
+    // motion is along the line between them
+    fn hydrogen_and_oxygen(
+        distance_pm: f32,
+        approach_speed: f32,
+    ) -> (Vec<Particle>, SpatialGrid, SimulationConfig) {
+        let config = SimulationConfig::default();
+        let hydrogen = find_element_index("H").unwrap();
+        let oxygen = find_element_index("O").unwrap();
+        let particles = vec![
+            Particle::new(
+                Vec2::new(approach_speed, 40_000f32),
+                0,
+                hydrogen as u8,
+                &elements()[hydrogen],
+                Vec2::new(-distance_pm / 2f32, 0f32),
+            ),
+            Particle::new(
+                Vec2::new(0f32, -25_000f32),
+                1,
+                oxygen as u8,
+                &elements()[oxygen],
+                Vec2::new(distance_pm / 2f32, 0f32),
+            ),
+        ];
+        let mut grid = SpatialGrid::new(config.box_size_pm, config.smoothing_distance.pm());
+        grid.rebuild(&particles, |particle| particle.position_pm, &config);
+        (particles, grid, config)
+    }
+    fn momentum(particles: &[Particle]) -> Vec2 {
+        particles
+            .iter()
+            .map(|particle| particle.velocity_pm_ns * particle.element().mass_u)
+            .sum()
+    }
+    fn total_energy(
+        particles: &[Particle],
+        grid: &SpatialGrid,
+        config: &SimulationConfig,
+    ) -> Energy {
+        kinetic_energy(particles) + local_potential_energy(particles, grid, &[0, 1], config)
+    }
+    fn assert_conserved(
+        (momentum_before, energy_before): (Vec2, Energy),
+        particles: &[Particle],
+        grid: &SpatialGrid,
+        config: &SimulationConfig,
+    ) {
+        let momentum_after = momentum(particles);
+        assert!(
+            momentum_after.distance(momentum_before) <= momentum_before.length() * 1e-5,
+            "momentum {momentum_before} -> {momentum_after}"
+        );
+        let energy_after = total_energy(particles, grid, config);
+        assert!(
+            (energy_after - energy_before).kj_per_mol().abs() < 0.1,
+            "energy {} -> {} kJ/mol",
+            energy_before.kj_per_mol(),
+            energy_after.kj_per_mol()
+        );
+    }
+
+    #[test]
+    fn forming_a_bond_conserves_momentum_and_energy() {
+        let (mut particles, grid, config) = hydrogen_and_oxygen(120f32, 6_000_000f32);
+        let before = (
+            momentum(&particles),
+            total_energy(&particles, &grid, &config),
+        );
+
+        assert!(form_bonds(&mut particles, &grid, &config));
+        assert!(particles[0].bonds.contains_key(&1));
+        assert!(particles[1].bonds.contains_key(&0));
+        assert_conserved(before, &particles, &grid, &config);
+        // the sideways motion isn't touched
+        assert_eq!(particles[0].velocity_pm_ns.y, 40_000f32);
+        assert_eq!(particles[1].velocity_pm_ns.y, -25_000f32);
+    }
+
+    #[test]
+    fn breaking_a_bond_conserves_momentum_and_energy() {
+        // flying apart harder than the bond holds
+        let (mut particles, grid, config) = hydrogen_and_oxygen(110f32, -40_000_000f32);
+        bond_pair(&mut particles, 0, 1, 1, BondType::Covalent, &config);
+        let before = (
+            momentum(&particles),
+            total_energy(&particles, &grid, &config),
+        );
+
+        assert!(dissociate_bonds(&mut particles, &grid, &config));
+        assert!(particles[0].bonds.is_empty());
+        assert!(particles[1].bonds.is_empty());
+        assert_conserved(before, &particles, &grid, &config);
+    }
+
+    #[test]
+    fn bond_holds_when_the_atoms_are_too_slow() {
+        let (mut particles, grid, config) = hydrogen_and_oxygen(110f32, -1_000_000f32);
+        bond_pair(&mut particles, 0, 1, 1, BondType::Covalent, &config);
+        let velocities: Vec<Vec2> = particles
+            .iter()
+            .map(|particle| particle.velocity_pm_ns)
+            .collect();
+
+        assert!(!dissociate_bonds(&mut particles, &grid, &config));
+        assert!(particles[0].bonds.contains_key(&1));
+        for (particle, velocity_pm_ns) in particles.iter().zip(velocities) {
+            assert_eq!(particle.velocity_pm_ns, velocity_pm_ns);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use bevy_math::Vec2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::SimulationConfig,
+    particle::Particle,
+    particle_physics::for_each_particle_mut,
+    random::{SimulationRng, particle_rng},
+    units::{Energy, Mass, Temperature},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Thermostat {
+    // no heat exchange, total energy is kept (NVE)
+    None,
+    // rescales velocities towards the target, quick but doesn't give correct fluctuations
+    Berendsen,
+    // friction plus random kicks, like the particles are in a heat bath
+    Langevin,
+    // extra friction variable that speeds up or slows down every particle, deterministic
+    NoseHoover,
+}
+impl Thermostat {
+    pub fn next(self) -> Thermostat {
+        match self {
+            Thermostat::None => Thermostat::Berendsen,
+            Thermostat::Berendsen => Thermostat::Langevin,
+            Thermostat::Langevin => Thermostat::NoseHoover,
+            Thermostat::NoseHoover => Thermostat::None,
+        }
+    }
+}
+
+// thermostat variables that have to live between steps
+#[derive(Clone, Default)]
+pub struct ThermostatState {
+    pub nose_hoover_friction: f32, // 1 / ns
+}
+
+// from the kinetic energy with 2 degrees of freedom per particle
+pub fn kinetic_temperature(particles: &[Particle]) -> Temperature {
+    if particles.is_empty() {
+        return Temperature::default();
+    }
+    let kinetic_energy: Energy = particles
+        .iter()
+        .map(|particle| {
+            Energy::kinetic(
+                Mass::from_u(particle.element().mass_u),
+                particle.velocity_pm_ns,
+            )
+        })
+        .sum();
+    Temperature::from_thermal_energy(kinetic_energy / particles.len() as f32)
+}
+
+pub fn apply_thermostat(
+    particles: &mut [Particle],
+    state: &mut ThermostatState,
+    rng: &mut SimulationRng,
+    delta_ns: f32,
+    config: &SimulationConfig,
+) {
+    let target = config.target_temperature_k;
+    let time_constant = config.thermostat_time_constant_ns.ns();
+    match config.thermostat {
+        Thermostat::None => {}
+        Thermostat::Berendsen => {
+            let temperature = kinetic_temperature(particles);
+            if temperature == Temperature::default() {
+                return;
+            }
+            // limited so a nearly frozen start doesn't explode
+            let scale = (1f32 + delta_ns / time_constant * (target / temperature - 1f32))
+                .max(0f32)
+                .sqrt()
+                .min(1.25f32);
+            for_each_particle_mut(particles, |particle| particle.velocity_pm_ns *= scale);
+        }
+        Thermostat::Langevin => {
+            let damping = (-delta_ns / time_constant).exp();
+            let noise = (1f32 - damping * damping).sqrt();
+            let pass_seed = rng.pass_seed();
+            for_each_particle_mut(particles, |particle| {
+                let thermal_speed = target.thermal_speed(Mass::from_u(particle.element().mass_u));
+                let mut rng = particle_rng(pass_seed, particle.index);
+                let kick = Vec2::new(standard_normal(&mut rng), standard_normal(&mut rng));
+                particle.velocity_pm_ns =
+                    particle.velocity_pm_ns * damping + kick * thermal_speed * noise;
+            });
+        }
+        Thermostat::NoseHoover => {
+            let temperature = kinetic_temperature(particles);
+            state.nose_hoover_friction +=
+                delta_ns / (time_constant * time_constant) * (temperature / target - 1f32);
+            let scale = (-state.nose_hoover_friction * delta_ns).exp();
+            for_each_particle_mut(particles, |particle| particle.velocity_pm_ns *= scale);
+        }
+    }
+}
+
+// Box-Muller transform
+pub fn standard_normal(rng: &mut impl Rng) -> f32 {
+    let uniform: f32 = 1f32 - rng.random::<f32>(); // (0, 1], ln(0) is undefined
+    let angle = rng.random::<f32>() * std::f32::consts::TAU;
+    (-2f32 * uniform.ln()).sqrt() * angle.cos()
+}
@@ -0,0 +1,34 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+// the only source of randomness of a simulation, owned by `Simulation` so a seed replays the
+// same run. ChaCha8 is a fixed algorithm, unlike `StdRng` and `SmallRng` it gives the same
+// numbers on every platform and rand version
+#[derive(Clone)]
+pub struct SimulationRng {
+    rng: ChaCha8Rng,
+}
+impl SimulationRng {
+    // random seed from the OS when `seed` is not set
+    pub fn new(seed: Option<u64>) -> SimulationRng {
+        let rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_os_rng(),
+        };
+        SimulationRng { rng }
+    }
+    pub fn rng(&mut self) -> &mut ChaCha8Rng {
+        &mut self.rng
+    }
+    // seed for one parallel pass, every particle builds its own rng from it and its index with
+    // `particle_rng` so the numbers don't depend on which thread handles which particle
+    pub fn pass_seed(&mut self) -> u64 {
+        self.rng.random()
+    }
+}
+// every particle reads its own ChaCha stream of the pass seed, the streams don't overlap
+pub fn particle_rng(pass_seed: u64, particle_index: usize) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(pass_seed);
+    rng.set_stream(particle_index as u64);
+    rng
+}
@@ -0,0 +1,168 @@
+use crate::{
+    config::SimulationConfig,
+    diagnostics,
+    particle::Particle,
+    particle_grid::SpatialGrid,
+    particle_physics, particles_spawning,
+    player_interaction_physics::{self, PlayerInteraction},
+    random::SimulationRng,
+    thermostat::{self, ThermostatState},
+    units::{Energy, Temperature, Time},
+};
+
+// adaptive timestep grows by this much per step
+const TIMESTEP_GROWTH: f32 = 1.1;
+// `advance` stops once less than this part of a timestep is left, rounding would otherwise leave a
+// sliver for one more tiny step
+const REMAINDER_TOLERANCE: f32 = 1e-3;
+
+// all simulated particles, usable without a bevy app:
+// `Simulation::spawn(config)` and then `step(delta)` or `advance(duration)` in a loop
+#[derive(Clone)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Resource))]
+pub struct Simulation {
+    pub particles: Vec<Particle>,
+    pub config: SimulationConfig,
+    pub thermostat_state: ThermostatState,
+    pub rng: SimulationRng,
+    pub elapsed_ns: f64,
+    pub steps: u64,
+    // added since the start, negative when energy was taken away
+    pub wall_work: Energy,
+    pub thermostat_work: Energy,
+    // length of the last step, smaller than `config.timestep_ns` when the adaptive timestep had to
+    // slow down
+    pub current_timestep: Time,
+    // at the current positions after every step, follows changes of the box size and cutoff in
+    // `config`
+    pub grid: SpatialGrid,
+}
+impl Simulation {
+    pub fn new(particles: Vec<Particle>, config: SimulationConfig) -> Simulation {
+        let mut grid = SpatialGrid::from_config(&config);
+        grid.rebuild(&particles, |particle| particle.position_pm, &config);
+        Simulation {
+            particles,
+            rng: SimulationRng::new(config.seed),
+            current_timestep: config.timestep_ns,
+            grid,
+            config,
+            thermostat_state: ThermostatState::default(),
+            elapsed_ns: 0f64,
+            steps: 0,
+            wall_work: Energy::default(),
+            thermostat_work: Energy::default(),
+        }
+    }
+    // uses the default spawning layout from `particles_spawning`
+    pub fn spawn(config: SimulationConfig) -> Simulation {
+        let mut rng = SimulationRng::new(config.seed);
+        let particles = particles_spawning::spawn_particles(&config, &mut rng);
+        // the rng keeps going from where spawning left it
+        Simulation {
+            rng,
+            ..Simulation::new(particles, config)
+        }
+    }
+    // advances the simulation by `duration` in one or more steps, with `adaptive_timestep` the
+    // steps get shorter while particles move or get pushed too far in one step
+    pub fn advance(&mut self, duration: Time) {
+        if !self.config.adaptive_timestep {
+            self.current_timestep = duration;
+            self.step(duration);
+            return;
+        }
+        let timestep_ns = self.config.timestep_ns.ns();
+        let min_timestep_ns = timestep_ns * self.config.min_timestep_fraction;
+        let mut remaining_ns = duration.ns();
+        while remaining_ns > timestep_ns * REMAINDER_TOLERANCE {
+            let delta_ns = self.current_timestep.ns().min(remaining_ns);
+            self.step(Time::from_ns(delta_ns));
+            remaining_ns -= delta_ns;
+
+            // shrink right away, grow back slowly once it is calm again
+            let stable_ns = particle_physics::stable_timestep(
+                &self.particles,
+                self.config.max_step_displacement_pm.pm(),
+            );
+            self.current_timestep = Time::from_ns(
+                (self.current_timestep.ns() * TIMESTEP_GROWTH)
+                    .min(stable_ns)
+                    .min(timestep_ns)
+                    .max(min_timestep_ns),
+            );
+        }
+    }
+    // advances the simulation by `delta`:
+    // resolves bonds, moves particles, bounces them of the box walls and applies the thermostat
+    pub fn step(&mut self, delta: Time) {
+        let delta_ns = delta.ns();
+        particle_physics::step_particles(
+            &mut self.particles,
+            &mut self.grid,
+            delta_ns,
+            &self.config,
+        );
+        self.wall_work += Energy::from_u_pm2_ns2(
+            self.particles
+                .iter()
+                .map(|particle| particle.wall_work)
+                .sum::<f32>(),
+        );
+
+        let kinetic_energy = diagnostics::kinetic_energy(&self.particles);
+        thermostat::apply_thermostat(
+            &mut self.particles,
+            &mut self.thermostat_state,
+            &mut self.rng,
+            delta_ns,
+            &self.config,
+        );
+        self.thermostat_work += diagnostics::kinetic_energy(&self.particles) - kinetic_energy;
+        self.elapsed_ns += delta_ns as f64;
+        self.steps += 1;
+    }
+    // particles that were outside of the box at the last step, they still interact with the
+    // particles in the closest edge cells of the grid
+    pub fn out_of_bounds_particles(&self) -> &[usize] {
+        self.grid.out_of_bounds()
+    }
+    pub fn temperature(&self) -> Temperature {
+        thermostat::kinetic_temperature(&self.particles)
+    }
+    pub fn apply_player_interaction(&mut self, interaction: PlayerInteraction, delta_ns: f32) {
+        player_interaction_physics::apply_player_interaction(
+            &mut self.particles,
+            interaction,
+            delta_ns,
+        );
+    }
+    // FNV-1a of every position and velocity bit, equal checksums mean the runs were identical
+    pub fn state_checksum(&self) -> u64 {
+        let mut checksum = 0xcbf2_9ce4_8422_2325u64;
+        for particle in &self.particles {
+            let values = [
+                particle.position_pm.x,
+                particle.position_pm.y,
+                particle.velocity_pm_ns.x,
+                particle.velocity_pm_ns.y,
+            ];
+            for byte in values
+                .iter()
+                .flat_map(|value| value.to_bits().to_le_bytes())
+            {
+                checksum ^= byte as u64;
+                checksum = checksum.wrapping_mul(0x0100_0000_01b3);
+            }
+        }
+        checksum
+    }
+    // every bond is stored on both of the bonded particles
+    pub fn bonds_count(&self) -> usize {
+        self.particles
+            .iter()
+            .map(|particle| particle.bonds.len())
+            .sum::<usize>()
+            / 2
+    }
+}
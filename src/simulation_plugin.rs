@@ -0,0 +1,178 @@
+use std::time::Duration;
+
+use bevy::{app::RunFixedMainLoopSystem, math::vec3, prelude::*, window::PrimaryWindow};
+
+use crate::{
+    config::SimulationConfig,
+    diagnostics::{self, EnergyHistory},
+    molecules::{self, Molecules},
+    player_interaction_physics::PlayerInteraction,
+    simulation::Simulation,
+    units::{pm_to_world, world_to_pm},
+};
+
+// thin bevy wrapper around `Simulation`, rendering is left to the app
+// `config` is inserted as a resource, changes to it are picked up by the simulation
+// physics runs in `FixedUpdate`, `Simulation::advance` by `timestep_ns` per fixed update
+#[derive(Default)]
+pub struct SimulationPlugin {
+    pub config: SimulationConfig,
+}
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.config.clone())
+            .insert_resource(Simulation::spawn(self.config.clone()))
+            .insert_resource(EnergyHistory::new(self.config.energy_history_length))
+            .init_resource::<Molecules>()
+            .add_systems(Startup, spawn_particle_entities)
+            .add_systems(
+                RunFixedMainLoop,
+                apply_config_changes.in_set(RunFixedMainLoopSystem::BeforeFixedMainLoop),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    handle_particles_physics,
+                    (record_energy, find_molecules, report_out_of_bounds),
+                )
+                    .chain(),
+            )
+            .add_systems(Update, sync_particle_transforms);
+    }
+}
+
+// camera the cursor is converted through for player interaction, the app has to spawn it
+#[derive(Component)]
+pub struct SimulationCamera;
+
+// links an entity to `Simulation::particles[index]`
+#[derive(Component)]
+pub struct ParticleEntity {
+    pub index: usize,
+}
+
+fn spawn_particle_entities(mut commands: Commands, simulation: Res<Simulation>) {
+    for particle in &simulation.particles {
+        commands.spawn((
+            ParticleEntity {
+                index: particle.index,
+            },
+            Transform::from_translation(pm_to_world(particle.position_pm).extend(0f32)),
+        ));
+    }
+}
+
+fn apply_config_changes(
+    config: Res<SimulationConfig>,
+    mut simulation: ResMut<Simulation>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    simulation.config = config.clone();
+
+    // real time between two physics steps
+    let step_time =
+        Duration::from_secs_f64(config.timestep_ns.ns() as f64 / config.time_scale_ns.ns() as f64);
+    fixed_time.set_timestep(step_time);
+    // bevy accumulates frame time and runs as many fixed updates as fit, a long frame would
+    // otherwise run a burst of steps and make the next frame even longer
+    virtual_time.set_max_delta(step_time * config.max_steps_per_frame);
+}
+
+pub fn handle_particles_physics(
+    mut simulation: ResMut<Simulation>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    // missing in headless mode
+    mouse_buttons: Option<Res<ButtonInput<MouseButton>>>,
+    q_camera: Query<(&Camera, &GlobalTransform), With<SimulationCamera>>,
+) {
+    let config = &simulation.config;
+    if !config.run_physics {
+        return;
+    }
+    let timestep = config.timestep_ns;
+    if let Some(interaction) =
+        read_player_interaction(&q_window, mouse_buttons.as_deref(), &q_camera)
+    {
+        simulation.apply_player_interaction(interaction, timestep.ns());
+    }
+    simulation.advance(timestep);
+}
+
+fn record_energy(simulation: Res<Simulation>, mut energy_history: ResMut<EnergyHistory>) {
+    if simulation.config.run_physics {
+        energy_history.push(diagnostics::measure_energy(&simulation));
+    }
+}
+
+fn find_molecules(simulation: Res<Simulation>, mut molecules: ResMut<Molecules>) {
+    if simulation.config.run_physics || molecules.molecule_ids.len() != simulation.particles.len() {
+        *molecules = molecules::detect_molecules(&simulation.particles);
+    }
+}
+
+// logs whenever the number of particles outside of the box changes
+fn report_out_of_bounds(simulation: Res<Simulation>, mut reported_count: Local<usize>) {
+    let out_of_bounds = simulation.out_of_bounds_particles();
+    if out_of_bounds.len() == *reported_count {
+        return;
+    }
+    *reported_count = out_of_bounds.len();
+    if out_of_bounds.is_empty() {
+        info!("all particles are back inside the box");
+    } else {
+        warn!(
+            "{} particles are outside the box: {:?}",
+            out_of_bounds.len(),
+            out_of_bounds
+        );
+    }
+}
+
+fn sync_particle_transforms(
+    simulation: Res<Simulation>,
+    mut particles: Query<(&ParticleEntity, &mut Transform)>,
+) {
+    particles
+        .iter_mut()
+        .for_each(|(particle_entity, mut transform)| {
+            let position = pm_to_world(simulation.particles[particle_entity.index].position_pm);
+            transform.translation = vec3(position.x, position.y, 0f32);
+        });
+}
+
+fn read_player_interaction(
+    q_window: &Query<&Window, With<PrimaryWindow>>,
+    mouse_buttons: Option<&ButtonInput<MouseButton>>,
+    q_camera: &Query<(&Camera, &GlobalTransform), With<SimulationCamera>>,
+) -> Option<PlayerInteraction> {
+    // get the camera info, transform and the primary window
+    // there is nothing to interact with when running headless
+    let (Ok((camera, camera_transform)), Ok(window), Some(mouse_buttons)) =
+        (q_camera.get_single(), q_window.get_single(), mouse_buttons)
+    else {
+        return None;
+    };
+
+    // check if the cursor is inside the window and get its position
+    // then, ask bevy to convert into world coordinates through the zoom and pan of the camera
+    let mouse_position = camera
+        .viewport_to_world_2d(camera_transform, window.cursor_position()?)
+        .map(world_to_pm)
+        .ok()?;
+
+    let force_sign = if mouse_buttons.pressed(MouseButton::Right) {
+        -1f32
+    } else {
+        // left click is disabled because not working good enough
+        return None;
+    };
+
+    Some(PlayerInteraction {
+        mouse_position,
+        force_sign,
+    })
+}
@@ -1,7 +1,92 @@
-use bevy::prelude::*;
+use std::collections::{BTreeMap, VecDeque};
+
+use bevy::{
+    prelude::*,
+    render::{camera::Viewport, view::RenderLayers},
+    window::PrimaryWindow,
+};
+use chemical_simulation::{
+    config::SimulationConfig, diagnostics::EnergyHistory, molecules::Molecules,
+    simulation::Simulation,
+};
+
+// samples kept for the species chart, one is taken every `CENSUS_SAMPLE_INTERVAL_SECS`
+const CENSUS_CHART_SAMPLES: usize = 120;
+const CENSUS_SAMPLE_INTERVAL_SECS: f32 = 0.1;
+// the most common formulas get a line on the chart, the rest is only listed
+const CENSUS_CHART_SPECIES: usize = 6;
+// formulas listed below the chart, the rest is summed up in one line
+const CENSUS_LISTED_SPECIES: usize = 12;
+const CENSUS_CHART_SIZE: Vec2 = Vec2::new(320f32, 160f32);
+// keeps the lines off the edge of the chart, in logical pixels
+const CENSUS_CHART_MARGIN: f32 = 4f32;
+const CENSUS_CHART_LINE_WIDTH: f32 = 2f32;
+// the chart camera only sees the chart lines
+const CENSUS_CHART_LAYER: usize = 1;
+const CENSUS_COLORS: [Color; CENSUS_CHART_SPECIES] = [
+    Color::srgb(0.95, 0.35, 0.3),
+    Color::srgb(0.3, 0.7, 0.95),
+    Color::srgb(0.45, 0.9, 0.4),
+    Color::srgb(0.95, 0.8, 0.3),
+    Color::srgb(0.8, 0.45, 0.95),
+    Color::srgb(0.95, 0.6, 0.85),
+];
 
 #[derive(Component)]
 pub struct FpsText;
+// one line of the formula list, all of them are spawned at the start and only rewritten
+#[derive(Component)]
+pub struct CensusTextLine(usize);
+#[derive(Component)]
+pub struct CensusChartTimeText;
+// background of the chart, the chart camera draws into its rectangle
+#[derive(Component)]
+pub struct CensusChart;
+#[derive(Component)]
+pub struct CensusChartCamera;
+// chart lines are gizmos on their own render layer
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct CensusChartGizmos;
+
+// molecule counts over simulated time
+#[derive(Resource)]
+pub struct CensusHistory {
+    samples: VecDeque<(f64, BTreeMap<String, usize>)>, // simulated time in ns, count per formula
+    sample_timer: Timer,
+}
+impl Default for CensusHistory {
+    fn default() -> Self {
+        Self {
+            samples: VecDeque::with_capacity(CENSUS_CHART_SAMPLES),
+            sample_timer: Timer::from_seconds(CENSUS_SAMPLE_INTERVAL_SECS, TimerMode::Repeating),
+        }
+    }
+}
+impl CensusHistory {
+    fn push(&mut self, time_ns: f64, census: BTreeMap<String, usize>) {
+        if self.samples.len() == CENSUS_CHART_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time_ns, census));
+    }
+    // formulas with the most molecules right now, the same count is sorted by the formula
+    fn charted_species(&self) -> Vec<&str> {
+        let Some((_, latest)) = self.samples.back() else {
+            return Vec::new();
+        };
+        let mut species: Vec<(&str, usize)> = latest
+            .iter()
+            .map(|(formula, count)| (formula.as_str(), *count))
+            .collect();
+        species.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        species
+            .into_iter()
+            .take(CENSUS_CHART_SPECIES)
+            .map(|(formula, _)| formula)
+            .collect()
+    }
+}
+
 pub fn setup_ui(commands: &mut Commands) {
     commands.spawn((
         Text::new("fps ->"),
@@ -13,9 +98,257 @@ pub fn setup_ui(commands: &mut Commands) {
         },
         FpsText {},
     ));
+
+    commands
+        .spawn(Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(12.),
+            right: Val::Px(12.),
+            flex_direction: FlexDirection::Column,
+            row_gap: Val::Px(6.),
+            ..default()
+        })
+        .with_children(|panel| {
+            panel.spawn(Text::new("molecules")).with_children(|text| {
+                for line in 0..=CENSUS_LISTED_SPECIES {
+                    text.spawn((TextSpan::default(), CensusTextLine(line)));
+                }
+            });
+            panel.spawn((
+                Node {
+                    width: Val::Px(CENSUS_CHART_SIZE.x),
+                    height: Val::Px(CENSUS_CHART_SIZE.y),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0., 0., 0., 0.5)),
+                CensusChart,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont::from_font_size(12.),
+                CensusChartTimeText,
+            ));
+        });
+
+    // drawn after the main camera and on top of it, the viewport follows the chart node
+    commands.spawn((
+        Camera2d,
+        Camera {
+            order: 1,
+            is_active: false,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        RenderLayers::layer(CENSUS_CHART_LAYER),
+        CensusChartCamera,
+    ));
+}
+pub fn configure_census_chart_gizmos(mut config_store: ResMut<GizmoConfigStore>) {
+    let (config, _) = config_store.config_mut::<CensusChartGizmos>();
+    config.render_layers = RenderLayers::layer(CENSUS_CHART_LAYER);
+    config.line_width = CENSUS_CHART_LINE_WIDTH;
 }
-pub fn update_ui(mut fps_text_query: Query<&mut Text, With<FpsText>>, time: Res<Time>) {
+pub fn update_ui(
+    mut fps_text_query: Query<&mut Text, With<FpsText>>,
+    time: Res<Time>,
+    config: Res<SimulationConfig>,
+    simulation: Res<Simulation>,
+    energy_history: Res<EnergyHistory>,
+) {
     let fps = (1f32 / time.delta_secs()).round();
     let mut fps_text = fps_text_query.single_mut();
-    fps_text.0 = format!("fps: {}", fps);
+    let energy = energy_history.latest().copied().unwrap_or_default();
+    fps_text.0 = format!(
+        "fps: {}\ntimestep: {:.3} fs\nintegrator: {:?} (I)\ntemperature: {:.0} K / {:.0} K\nthermostat: {:?} (T)\nenergy: {:.1} kJ/mol (E saves)\nview: scroll zooms, drag pans, F follows, Home resets",
+        fps,
+        simulation.current_timestep.fs(),
+        config.integrator,
+        simulation.temperature().k(),
+        config.target_temperature_k.k(),
+        config.thermostat,
+        energy.total().kj_per_mol()
+    );
+}
+// lets the integrators be compared on the same run
+pub fn switch_integrator(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<SimulationConfig>) {
+    if keys.just_pressed(KeyCode::KeyI) {
+        config.integrator = config.integrator.next();
+    }
+}
+pub fn switch_thermostat(keys: Res<ButtonInput<KeyCode>>, mut config: ResMut<SimulationConfig>) {
+    if keys.just_pressed(KeyCode::KeyT) {
+        config.thermostat = config.thermostat.next();
+    }
+}
+pub fn save_energy_log(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<SimulationConfig>,
+    energy_history: Res<EnergyHistory>,
+) {
+    if !keys.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    let Some(path) = &config.energy_log_path else {
+        warn!("set energy_log_path in the config to save the energy history");
+        return;
+    };
+    match energy_history.write_csv(path) {
+        Ok(()) => info!("energy history saved to {path}"),
+        Err(error) => error!("{error:#}"),
+    }
+}
+
+pub fn record_census(
+    time: Res<Time>,
+    simulation: Res<Simulation>,
+    molecules: Res<Molecules>,
+    mut census_history: ResMut<CensusHistory>,
+) {
+    if !census_history
+        .sample_timer
+        .tick(time.delta())
+        .just_finished()
+    {
+        return;
+    }
+    // a paused simulation would only flatten the chart
+    if census_history
+        .samples
+        .back()
+        .is_some_and(|(time_ns, _)| *time_ns == simulation.elapsed_ns)
+    {
+        return;
+    }
+    let census = molecules
+        .census()
+        .into_iter()
+        .map(|(formula, count)| (formula.to_owned(), count))
+        .collect();
+    census_history.push(simulation.elapsed_ns, census);
+}
+pub fn update_census_ui(
+    census_history: Res<CensusHistory>,
+    mut census_lines_query: Query<(&CensusTextLine, &mut TextSpan, &mut TextColor)>,
+    mut chart_time_text_query: Query<&mut Text, With<CensusChartTimeText>>,
+) {
+    if !census_history.is_changed() {
+        return;
+    }
+    let Some((latest_time_ns, latest)) = census_history.samples.back() else {
+        return;
+    };
+    let charted_species = census_history.charted_species();
+
+    // the most common formulas get a line, the charted ones in the color of their chart line
+    let mut listed: Vec<(&str, usize)> = latest
+        .iter()
+        .map(|(formula, count)| (formula.as_str(), *count))
+        .collect();
+    listed.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    for (line, mut span, mut color) in &mut census_lines_query {
+        let (text, line_color) = match listed.get(line.0) {
+            Some((formula, count)) if line.0 < CENSUS_LISTED_SPECIES => (
+                format!("\n{formula}: {count}"),
+                charted_species
+                    .iter()
+                    .position(|charted| charted == formula)
+                    .map_or(Color::WHITE, |species| CENSUS_COLORS[species]),
+            ),
+            Some(_) => (
+                format!("\n{} more", listed.len() - CENSUS_LISTED_SPECIES),
+                Color::WHITE,
+            ),
+            None => (String::new(), Color::WHITE),
+        };
+        if span.0 != text {
+            span.0 = text;
+        }
+        color.0 = line_color;
+    }
+
+    let first_time_ns = census_history
+        .samples
+        .front()
+        .map_or(0f64, |sample| sample.0);
+    chart_time_text_query.single_mut().0 = format!(
+        "simulated time: {:.1} ps - {:.1} ps",
+        first_time_ns * 1e3f64,
+        latest_time_ns * 1e3f64
+    );
+}
+// moves the chart camera over the chart node, the node moves when the formula list changes length
+pub fn place_census_chart_camera(
+    chart_query: Query<(&ComputedNode, &GlobalTransform), With<CensusChart>>,
+    mut camera_query: Query<&mut Camera, With<CensusChartCamera>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let (Ok((node, node_transform)), Ok(mut camera), Ok(window)) = (
+        chart_query.get_single(),
+        camera_query.get_single_mut(),
+        q_window.get_single(),
+    ) else {
+        return;
+    };
+    // ui nodes are laid out in physical pixels, a viewport outside of the window would fail
+    let window_size = window.physical_size();
+    let top_left = (node_transform.translation().truncate() - node.size() / 2f32)
+        .max(Vec2::ZERO)
+        .as_uvec2()
+        .min(window_size);
+    let size = node.size().as_uvec2().min(window_size - top_left);
+    let rect = (size.x > 0 && size.y > 0).then_some((top_left, size));
+
+    // only touch the camera when the rectangle changed, every change rebuilds its projection
+    let current_rect = camera
+        .viewport
+        .as_ref()
+        .filter(|_| camera.is_active)
+        .map(|viewport| (viewport.physical_position, viewport.physical_size));
+    if current_rect != rect {
+        camera.is_active = rect.is_some();
+        camera.viewport = rect.map(|(physical_position, physical_size)| Viewport {
+            physical_position,
+            physical_size,
+            ..default()
+        });
+    }
+}
+// one line per charted formula, the chart camera is centered on the chart so (0, 0) is its middle.
+// Points are placed by their simulated time, samples are taken in real time and dropped steps or a
+// changed time scale would bend the time axis otherwise
+pub fn draw_census_chart(
+    census_history: Res<CensusHistory>,
+    mut gizmos: Gizmos<CensusChartGizmos>,
+) {
+    let charted_species = census_history.charted_species();
+    let max_count = census_history
+        .samples
+        .iter()
+        .flat_map(|(_, census)| {
+            charted_species
+                .iter()
+                .filter_map(|formula| census.get(*formula).copied())
+        })
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let area = CENSUS_CHART_SIZE - Vec2::splat(2f32 * CENSUS_CHART_MARGIN);
+    let (Some((first_time_ns, _)), Some((latest_time_ns, _))) = (
+        census_history.samples.front(),
+        census_history.samples.back(),
+    ) else {
+        return;
+    };
+    // a single sample sits on the left edge
+    let duration_ns = (latest_time_ns - first_time_ns).max(f64::MIN_POSITIVE);
+    for (species, formula) in charted_species.iter().enumerate() {
+        let points = census_history.samples.iter().map(|(time_ns, census)| {
+            let count = census.get(*formula).copied().unwrap_or(0);
+            Vec2::new(
+                ((time_ns - first_time_ns) / duration_ns) as f32 * area.x,
+                count as f32 / max_count as f32 * area.y,
+            ) - area / 2f32
+        });
+        gizmos.linestrip_2d(points, CENSUS_COLORS[species]);
+    }
 }
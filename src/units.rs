@@ -0,0 +1,246 @@
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+use bevy_math::Vec2;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// the simulation works in pm, ns, u (Da) and e, energy follows from them as u * pm^2 / ns^2 and is
+// counted per mole. Hot loops use plain f32 in these units, everything crossing a module boundary
+// (config, tables, diagnostics) uses the types below so a value can't be read in the wrong unit
+
+// u * pm^2 / ns^2
+pub const KJ_PER_MOL: f32 = 1e12;
+// 1 eV per particle is 96.485 kJ/mol
+pub const ELECTRON_VOLT: f32 = 96.485_33 * KJ_PER_MOL;
+// molar gas constant in u * pm^2 / ns^2 / K, the simulation counts energy per mole
+pub const BOLTZMANN_CONSTANT: f32 = 8.314_462_6e9;
+// 138.935 kJ/mol * nm / e^2 converted to (u * pm^2 / ns^2) * pm / e^2
+pub const COULOMB_CONSTANT: f32 = 1.389_354_6e17;
+pub const FS_PER_NS: f32 = 1e6;
+// world units (pixels with an unzoomed camera) per pm, the only scale between the simulation and
+// the screen
+pub const WORLD_UNITS_PER_PM: f32 = 1f32;
+
+pub fn pm_to_world(position_pm: Vec2) -> Vec2 {
+    position_pm * WORLD_UNITS_PER_PM
+}
+pub fn world_to_pm(world_position: Vec2) -> Vec2 {
+    world_position / WORLD_UNITS_PER_PM
+}
+
+// arithmetic that keeps the unit, multiplying two values of the same unit would change it so only
+// scaling by a number and dividing into a ratio are allowed
+macro_rules! unit {
+    ($name:ident) => {
+        impl Add for $name {
+            type Output = $name;
+            fn add(self, other: $name) -> $name {
+                $name(self.0 + other.0)
+            }
+        }
+        impl Sub for $name {
+            type Output = $name;
+            fn sub(self, other: $name) -> $name {
+                $name(self.0 - other.0)
+            }
+        }
+        impl AddAssign for $name {
+            fn add_assign(&mut self, other: $name) {
+                self.0 += other.0;
+            }
+        }
+        impl SubAssign for $name {
+            fn sub_assign(&mut self, other: $name) {
+                self.0 -= other.0;
+            }
+        }
+        impl Neg for $name {
+            type Output = $name;
+            fn neg(self) -> $name {
+                $name(-self.0)
+            }
+        }
+        impl Mul<f32> for $name {
+            type Output = $name;
+            fn mul(self, scale: f32) -> $name {
+                $name(self.0 * scale)
+            }
+        }
+        impl Div<f32> for $name {
+            type Output = $name;
+            fn div(self, scale: f32) -> $name {
+                $name(self.0 / scale)
+            }
+        }
+        impl Div for $name {
+            type Output = f32;
+            fn div(self, other: $name) -> f32 {
+                self.0 / other.0
+            }
+        }
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = $name>>(iter: I) -> $name {
+                $name(iter.map(|value| value.0).sum())
+            }
+        }
+    };
+}
+
+// stored and written to config files in pm
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Length(f32);
+unit!(Length);
+impl Length {
+    pub const fn from_pm(pm: f32) -> Length {
+        Length(pm)
+    }
+    pub fn pm(self) -> f32 {
+        self.0
+    }
+}
+
+// stored and written to config files in ns
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Time(f32);
+unit!(Time);
+impl Time {
+    pub const fn from_ns(ns: f32) -> Time {
+        Time(ns)
+    }
+    pub fn from_fs(fs: f32) -> Time {
+        Time(fs / FS_PER_NS)
+    }
+    pub fn ns(self) -> f32 {
+        self.0
+    }
+    pub fn fs(self) -> f32 {
+        self.0 * FS_PER_NS
+    }
+}
+
+// stored in u (Da)
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Mass(f32);
+unit!(Mass);
+impl Mass {
+    pub const fn from_u(u: f32) -> Mass {
+        Mass(u)
+    }
+    pub fn u(self) -> f32 {
+        self.0
+    }
+}
+
+// stored in u * pm^2 / ns^2 per mole, written to config files in kJ/mol
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug)]
+pub struct Energy(f32);
+unit!(Energy);
+impl Energy {
+    pub const fn from_u_pm2_ns2(u_pm2_ns2: f32) -> Energy {
+        Energy(u_pm2_ns2)
+    }
+    pub const fn from_kj_per_mol(kj_per_mol: f32) -> Energy {
+        Energy(kj_per_mol * KJ_PER_MOL)
+    }
+    pub const fn from_ev(ev: f32) -> Energy {
+        Energy(ev * ELECTRON_VOLT)
+    }
+    pub fn u_pm2_ns2(self) -> f32 {
+        self.0
+    }
+    pub fn kj_per_mol(self) -> f32 {
+        self.0 / KJ_PER_MOL
+    }
+    pub fn ev(self) -> f32 {
+        self.0 / ELECTRON_VOLT
+    }
+    pub fn kinetic(mass: Mass, velocity_pm_ns: Vec2) -> Energy {
+        Energy(mass.0 / 2f32 * velocity_pm_ns.length_squared())
+    }
+}
+impl Serialize for Energy {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.kj_per_mol().serialize(serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Energy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Energy, D::Error> {
+        f32::deserialize(deserializer).map(Energy::from_kj_per_mol)
+    }
+}
+
+// stored and written to config files in K
+#[derive(Clone, Copy, PartialEq, PartialOrd, Default, Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Temperature(f32);
+unit!(Temperature);
+impl Temperature {
+    pub const fn from_k(k: f32) -> Temperature {
+        Temperature(k)
+    }
+    pub fn k(self) -> f32 {
+        self.0
+    }
+    // k_B * T, per mole like every other energy
+    pub fn thermal_energy(self) -> Energy {
+        Energy(BOLTZMANN_CONSTANT * self.0)
+    }
+    pub fn from_thermal_energy(energy: Energy) -> Temperature {
+        Temperature(energy.0 / BOLTZMANN_CONSTANT)
+    }
+    // pm / ns, spread of one velocity component of the Maxwell-Boltzmann distribution
+    pub fn thermal_speed(self, mass: Mass) -> f32 {
+        (self.thermal_energy().0 / mass.0).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value / expected - 1f32).abs() < 1e-5,
+            "{value} isn't close to {expected}"
+        );
+    }
+
+    #[test]
+    fn energy_converts_between_kj_per_mol_and_internal_units() {
+        let energy = Energy::from_kj_per_mol(2.5);
+        assert_close(energy.u_pm2_ns2(), 2.5e12);
+        assert_close(energy.kj_per_mol(), 2.5);
+        assert_close(Energy::from_ev(1f32).kj_per_mol(), 96.485_33);
+    }
+
+    #[test]
+    fn temperature_round_trips_through_thermal_energy() {
+        let temperature = Temperature::from_k(300f32);
+        // R * T at room temperature
+        assert_close(temperature.thermal_energy().kj_per_mol(), 2.494_339);
+        assert_close(
+            Temperature::from_thermal_energy(temperature.thermal_energy()).k(),
+            300f32,
+        );
+    }
+
+    #[test]
+    fn thermal_speed_follows_the_mass() {
+        // sqrt(R * T / M) for one velocity component of water at 300 K, about 372 m/s
+        let speed_pm_ns = Temperature::from_k(300f32).thermal_speed(Mass::from_u(18.015));
+        assert_close(speed_pm_ns, 372_100f32);
+        let heavier_speed_pm_ns = Temperature::from_k(300f32).thermal_speed(Mass::from_u(72.06));
+        assert_close(heavier_speed_pm_ns * 2f32, speed_pm_ns);
+    }
+
+    #[test]
+    fn world_position_round_trips() {
+        let position_pm = Vec2::new(-1234.5, 678.25);
+        assert_eq!(world_to_pm(pm_to_world(position_pm)), position_pm);
+    }
+}
@@ -0,0 +1,46 @@
+use bevy_math::Vec2;
+use chemical_simulation::{
+    config::SimulationConfig, simulation::Simulation, thermostat::Thermostat, units::Time,
+};
+
+fn simulation() -> Simulation {
+    Simulation::spawn(SimulationConfig {
+        seed: Some(1),
+        thermostat: Thermostat::None,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn advance_moves_time_by_the_duration() {
+    let mut simulation = simulation();
+    let timestep_ns = simulation.config.timestep_ns.ns();
+    // a duration that isn't a whole number of steps
+    let duration = Time::from_ns(timestep_ns * 7.3);
+    simulation.advance(duration);
+    let tolerance_ns = (timestep_ns * 1e-3) as f64;
+    assert!((simulation.elapsed_ns - duration.ns() as f64).abs() <= tolerance_ns);
+
+    simulation.advance(duration);
+    assert!((simulation.elapsed_ns - 2f64 * duration.ns() as f64).abs() <= 2f64 * tolerance_ns);
+}
+
+#[test]
+fn timestep_shrinks_for_fast_particles() {
+    let mut simulation = simulation();
+    let timestep = simulation.config.timestep_ns;
+    simulation.advance(timestep);
+    assert_eq!(simulation.current_timestep, timestep);
+
+    // would cross many times the allowed distance in one full step
+    let max_step_displacement_pm = simulation.config.max_step_displacement_pm.pm();
+    simulation.particles[0].velocity_pm_ns =
+        Vec2::X * 100f32 * max_step_displacement_pm / timestep.ns();
+    simulation.advance(timestep);
+    assert!(simulation.current_timestep < timestep);
+
+    // the next duration gets split into the shorter steps
+    let steps = simulation.steps;
+    simulation.advance(timestep);
+    assert!(simulation.steps > steps + 1);
+}
@@ -0,0 +1,73 @@
+use bevy_math::Vec2;
+use chemical_simulation::{
+    config::SimulationConfig,
+    element::{elements, find_element_index},
+    particle::Particle,
+    simulation::Simulation,
+    thermostat::Thermostat,
+    units::{Energy, Mass},
+};
+
+// long enough to collide and fly apart, not long enough to reach the walls
+const STEPS: usize = 150;
+
+// an H and an O flying head on, with `collision_energy` in their motion towards each other. True
+// when they were bonded after any of the steps, two atoms alone have more energy than the bond
+// holds so they fly apart again, keeping them together takes a third atom or the thermostat
+fn head_on_collision(collision_energy: Energy, config: SimulationConfig) -> (Simulation, bool) {
+    let hydrogen = find_element_index("H").unwrap();
+    let oxygen = find_element_index("O").unwrap();
+    let (hydrogen_mass, oxygen_mass) = (elements()[hydrogen].mass_u, elements()[oxygen].mass_u);
+    let reduced_mass = Mass::from_u(hydrogen_mass * oxygen_mass / (hydrogen_mass + oxygen_mass));
+    let speed = (2f32 * collision_energy.u_pm2_ns2() / reduced_mass.u()).sqrt();
+    // no momentum in total, so the pair stays around the middle of the box
+    let particles = vec![
+        Particle::new(
+            Vec2::X * speed * oxygen_mass / (hydrogen_mass + oxygen_mass),
+            0,
+            hydrogen as u8,
+            &elements()[hydrogen],
+            Vec2::new(-400f32, 0f32),
+        ),
+        Particle::new(
+            -Vec2::X * speed * hydrogen_mass / (hydrogen_mass + oxygen_mass),
+            1,
+            oxygen as u8,
+            &elements()[oxygen],
+            Vec2::new(400f32, 0f32),
+        ),
+    ];
+    let mut simulation = Simulation::new(
+        particles,
+        SimulationConfig {
+            thermostat: Thermostat::None,
+            ..config
+        },
+    );
+    let timestep = simulation.config.timestep_ns;
+    let mut bonded = false;
+    for _ in 0..STEPS {
+        simulation.advance(timestep);
+        bonded |= simulation.particles[0].bonds.contains_key(&1);
+    }
+    (simulation, bonded)
+}
+
+#[test]
+fn collision_above_the_activation_energy_forms_a_bond() {
+    let (_, bonded) = head_on_collision(Energy::from_kj_per_mol(150f32), Default::default());
+    assert!(bonded);
+}
+
+#[test]
+fn collision_below_the_activation_energy_bounces_off() {
+    let config = SimulationConfig {
+        reaction_activation_energy_kj_mol: Energy::from_kj_per_mol(400f32),
+        ..Default::default()
+    };
+    let (simulation, bonded) = head_on_collision(Energy::from_kj_per_mol(150f32), config);
+    assert!(!bonded);
+    // the van der Waals wall turned them around
+    assert!(simulation.particles[0].velocity_pm_ns.x < 0f32);
+    assert!(simulation.particles[1].velocity_pm_ns.x > 0f32);
+}
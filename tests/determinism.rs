@@ -0,0 +1,30 @@
+use chemical_simulation::{
+    config::SimulationConfig, simulation::Simulation, thermostat::Thermostat,
+};
+
+const STEPS: usize = 200;
+
+// Langevin draws from the per-particle streams every step, spawning from the main rng
+fn checksum_after_steps(seed: u64) -> u64 {
+    let config = SimulationConfig {
+        seed: Some(seed),
+        thermostat: Thermostat::Langevin,
+        ..Default::default()
+    };
+    let mut simulation = Simulation::spawn(config);
+    let timestep = simulation.config.timestep_ns;
+    for _ in 0..STEPS {
+        simulation.advance(timestep);
+    }
+    simulation.state_checksum()
+}
+
+#[test]
+fn same_seed_replays_the_same_run() {
+    assert_eq!(checksum_after_steps(42), checksum_after_steps(42));
+}
+
+#[test]
+fn different_seeds_give_different_runs() {
+    assert_ne!(checksum_after_steps(42), checksum_after_steps(43));
+}